@@ -5,14 +5,97 @@ pub trait Serde: Sized {
     fn from_json(json: &str) -> Result<Self, String>;
 }
 
+// Splits the inside of an array or object at the `separator`s that are not
+// within a string or a nested array or object.
+fn split_top_level(json: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (index, c) in json.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => (),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '[' | '{' => depth += 1,
+            ']' | '}' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&json[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&json[start..]);
+    parts
+}
+
+fn unescape(content: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(content.len());
+    let mut chars = content.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Err("Unescaped quote in JSON string".to_string()),
+            '\\' => {
+                let c = match chars.next().ok_or("Unterminated escape in JSON string")? {
+                    '"' => '"',
+                    '\\' => '\\',
+                    '/' => '/',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'u' => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        let code = u32::from_str_radix(&hex, 16).map_err(|e| e.to_string())?;
+                        char::from_u32(code).ok_or("Invalid unicode escape in JSON string")?
+                    }
+                    c => return Err(format!("Invalid escape '\\{}' in JSON string", c)),
+                };
+                unescaped.push(c);
+            }
+            c => unescaped.push(c),
+        }
+    }
+
+    Ok(unescaped)
+}
+
 impl Serde for String {
     fn to_json(&self) -> String {
-        format!("\"{}\"", self)
+        let mut json = String::with_capacity(self.len() + 2);
+        json.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => json.push_str("\\\""),
+                '\\' => json.push_str("\\\\"),
+                '\n' => json.push_str("\\n"),
+                '\r' => json.push_str("\\r"),
+                '\t' => json.push_str("\\t"),
+                c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+                c => json.push(c),
+            }
+        }
+        json.push('"');
+        json
     }
 
     fn from_json(json: &str) -> Result<Self, String> {
-        if json.starts_with('"') && json.ends_with('"') {
-            Ok(json[1..json.len()-1].to_string())
+        if json.len() >= 2 && json.starts_with('"') && json.ends_with('"') {
+            unescape(&json[1..json.len()-1])
         } else {
             Err("Invalid JSON string".to_string())
         }
@@ -49,7 +132,10 @@ impl<T: Serde> Serde for Vec<T> {
         if json.starts_with('[') && json.ends_with(']') {
             let elements = &json[1..json.len()-1];
             let mut vec = Vec::new();
-            for element in elements.split(',') {
+            if elements.trim().is_empty() {
+                return Ok(vec);
+            }
+            for element in split_top_level(elements, ',') {
                 vec.push(T::from_json(element.trim())?);
             }
             Ok(vec)
@@ -71,10 +157,17 @@ impl<K: Serde + Eq + std::hash::Hash, V: Serde> Serde for HashMap<K, V> {
         if json.starts_with('{') && json.ends_with('}') {
             let elements = &json[1..json.len()-1];
             let mut map = HashMap::new();
-            for element in elements.split(',') {
-                let mut kv = element.splitn(2, ':');
-                let key = kv.next().ok_or("Missing key")?.trim();
-                let value = kv.next().ok_or("Missing value")?.trim();
+            if elements.trim().is_empty() {
+                return Ok(map);
+            }
+            for element in split_top_level(elements, ',') {
+                let kv = split_top_level(element, ':');
+                let key = kv.first().ok_or("Missing key")?.trim();
+                let value = match kv.as_slice() {
+                    [_, value] => value.trim(),
+                    [_] => return Err("Missing value".to_string()),
+                    _ => return Err("Unexpected ':' in JSON object".to_string()),
+                };
                 map.insert(K::from_json(key)?, V::from_json(value)?);
             }
             Ok(map)
@@ -86,4 +179,5 @@ impl<K: Serde + Eq + std::hash::Hash, V: Serde> Serde for HashMap<K, V> {
 
 #[cfg(test)]
 #[path = "./json_test.rs"]
+#[allow(clippy::bool_assert_comparison)]
 mod test;
\ No newline at end of file
@@ -75,4 +75,33 @@ mod tests {
         assert_eq!(my_map.get("key1").unwrap(), "value1");
         assert_eq!(my_map.get("key2").unwrap(), "value2");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_string_escapes() {
+        let my_string = "say \"hi\"\\\n".to_string();
+        assert_eq!(my_string.to_json(), "\"say \\\"hi\\\"\\\\\\n\"");
+        let parsed: String = Serde::from_json(&my_string.to_json()).unwrap();
+        assert_eq!(parsed, my_string);
+        assert!(String::from_json("\"a\"b\"").is_err());
+    }
+
+    #[test]
+    fn test_commas_and_colons_in_strings() {
+        let json_vec = "[\"a, b\", \"c: d\"]";
+        let my_vec: Vec<String> = Serde::from_json(json_vec).unwrap();
+        assert_eq!(my_vec, vec!["a, b", "c: d"]);
+
+        let mut my_map = HashMap::new();
+        my_map.insert("key: 1".to_string(), "a, \"b\": c".to_string());
+        let parsed: HashMap<String, String> = Serde::from_json(&my_map.to_json()).unwrap();
+        assert_eq!(parsed, my_map);
+    }
+
+    #[test]
+    fn test_nested_and_empty() {
+        let json_map = "{\"a\": [\"1\", \"2\"], \"b\": []}";
+        let my_map: HashMap<String, Vec<String>> = Serde::from_json(json_map).unwrap();
+        assert_eq!(my_map["a"], vec!["1", "2"]);
+        assert!(my_map["b"].is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom, Write},
+    net::TcpStream,
+};
+
+use logger::debug;
+
+use crate::{compression::Encoder, http::Encoding, CRLF};
+
+// Size of the chunks a streamed reader is split into.
+pub(crate) static READ_CHUNK_SIZE: usize = 8 * 1024;
+
+type Chunks = Box<dyn Iterator<Item = io::Result<Vec<u8>>> + Send>;
+
+/// A region of an open file, sent without being loaded into memory.
+pub struct FileBody {
+    file: File,
+    offset: u64,
+    length: u64,
+}
+
+pub enum Body {
+    Bytes(Vec<u8>),
+    File(FileBody),
+    /// Content of unknown length, sent with chunked transfer encoding.
+    Stream(Chunks),
+}
+
+impl Body {
+    /// Creates a body covering the whole of `file`.
+    pub fn file(file: File) -> io::Result<Body> {
+        let length = file.metadata()?.len();
+        Ok(Body::File(FileBody {
+            file,
+            offset: 0,
+            length,
+        }))
+    }
+
+    /// Creates a streamed body from an iterator of chunks.
+    pub fn stream<I>(chunks: I) -> Body
+    where
+        I: Iterator<Item = Vec<u8>> + Send + 'static,
+    {
+        Body::Stream(Box::new(chunks.map(Ok)))
+    }
+
+    /// Creates a streamed body that reads `reader` until EOF.
+    pub fn reader<R>(mut reader: R) -> Body
+    where
+        R: Read + Send + 'static,
+    {
+        let chunks = std::iter::from_fn(move || {
+            let mut chunk = vec![0; READ_CHUNK_SIZE];
+            match reader.read(&mut chunk) {
+                Ok(0) => None,
+                Ok(read) => {
+                    chunk.truncate(read);
+                    Some(Ok(chunk))
+                }
+                Err(e) => Some(Err(e)),
+            }
+        });
+
+        Body::Stream(Box::new(chunks))
+    }
+
+    /// Returns the length of the content, or `None` for streamed bodies.
+    pub fn size(&self) -> Option<u64> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes.len() as u64),
+            Body::File(file) => Some(file.length),
+            Body::Stream(_) => None,
+        }
+    }
+
+    /// Returns the in-memory content, or `None` for file-backed bodies.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Body::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Reads the inclusive byte range `start..=end` into memory.
+    pub fn read_range(&self, start: u64, end: u64) -> io::Result<Vec<u8>> {
+        match self {
+            Body::Bytes(bytes) => Ok(bytes[start as usize..=end as usize].to_vec()),
+            Body::File(body) => {
+                let mut content = vec![0; (end - start + 1) as usize];
+                let mut file = &body.file;
+                file.seek(SeekFrom::Start(body.offset + start))?;
+                file.read_exact(&mut content)?;
+                Ok(content)
+            }
+            Body::Stream(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "streamed bodies cannot be read by range",
+            )),
+        }
+    }
+
+    /// Narrows the body down to the inclusive byte range `start..=end`.
+    pub fn slice(self, start: u64, end: u64) -> io::Result<Body> {
+        match self {
+            Body::Bytes(_) => Ok(Body::Bytes(self.read_range(start, end)?)),
+            Body::File(body) => Ok(Body::File(FileBody {
+                file: body.file,
+                offset: body.offset + start,
+                length: end - start + 1,
+            })),
+            Body::Stream(_) => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "streamed bodies cannot be sliced",
+            )),
+        }
+    }
+
+    pub fn into_bytes(self) -> io::Result<Vec<u8>> {
+        match self {
+            Body::Bytes(bytes) => Ok(bytes),
+            Body::File(ref body) if body.length == 0 => Ok(Vec::new()),
+            Body::File(ref body) => self.read_range(0, body.length - 1),
+            Body::Stream(chunks) => chunks
+                .collect::<io::Result<Vec<Vec<u8>>>>()
+                .map(|c| c.concat()),
+        }
+    }
+
+    /// Writes the content to the stream. Streamed bodies are written as chunks
+    /// followed by the trailers, compressing them on the fly with `encoding` at
+    /// the given level. Without trailers they are written as is, for HTTP/1.0
+    /// clients which read them until the connection closes.
+    pub fn write_to(
+        &mut self,
+        stream: &mut TcpStream,
+        encoding: Option<(&Encoding, u32)>,
+        trailers: Option<&[String]>,
+    ) -> io::Result<()> {
+        match self {
+            Body::Bytes(bytes) => stream.write_all(bytes),
+            Body::File(body) => body.write_to(stream),
+            Body::Stream(chunks) => write_stream(stream, chunks, encoding, trailers),
+        }
+    }
+}
+
+fn write_chunk<W: Write>(writer: &mut W, chunk: &[u8]) -> io::Result<()> {
+    // An empty chunk would mark the end of the body
+    if chunk.is_empty() {
+        return Ok(());
+    }
+
+    write!(writer, "{:X}{}", chunk.len(), CRLF)?;
+    writer.write_all(chunk)?;
+    writer.write_all(CRLF.as_bytes())
+}
+
+fn write_stream<W: Write>(
+    writer: &mut W,
+    chunks: &mut Chunks,
+    encoding: Option<(&Encoding, u32)>,
+    trailers: Option<&[String]>,
+) -> io::Result<()> {
+    let chunked = trailers.is_some();
+    let write = |writer: &mut W, chunk: &[u8]| match chunked {
+        true => write_chunk(writer, chunk),
+        false => writer.write_all(chunk),
+    };
+    let mut encoder = encoding.and_then(|(encoding, level)| Encoder::new(encoding, level));
+
+    for chunk in chunks {
+        let chunk = chunk?;
+
+        match encoder.as_mut() {
+            Some(encoder) => {
+                encoder.write(&chunk)?;
+                write(writer, &encoder.take())?;
+            }
+            None => write(writer, &chunk)?,
+        }
+    }
+
+    if let Some(encoder) = encoder {
+        write(writer, &encoder.finish()?)?;
+    }
+
+    if let Some(trailers) = trailers {
+        write!(writer, "0{}", CRLF)?;
+        for trailer in trailers {
+            write!(writer, "{}{}", trailer, CRLF)?;
+        }
+        write!(writer, "{}", CRLF)?;
+    }
+
+    writer.flush()
+}
+
+impl FileBody {
+    fn write_to(&self, stream: &mut TcpStream) -> io::Result<()> {
+        let sent = sendfile(stream, &self.file, self.offset, self.length)?;
+
+        if sent < self.length {
+            debug!(
+                "sendfile unavailable after {} bytes, copying through userspace",
+                sent
+            );
+            let mut file = &self.file;
+            file.seek(SeekFrom::Start(self.offset + sent))?;
+            let copied = io::copy(&mut file.take(self.length - sent), stream)?;
+
+            if sent + copied < self.length {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "file shrank while it was being sent",
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// Sends the file region straight from the page cache to the socket. Returns
+// the number of bytes sent, which is short if sendfile(2) is not supported for
+// this file so the caller can finish with a buffered copy.
+#[cfg(target_os = "linux")]
+fn sendfile(stream: &TcpStream, file: &File, offset: u64, length: u64) -> io::Result<u64> {
+    use std::os::unix::io::AsRawFd;
+
+    extern "C" {
+        fn sendfile(out_fd: i32, in_fd: i32, offset: *mut i64, count: usize) -> isize;
+    }
+
+    // Linux transfers at most this many bytes per call
+    const MAX_CHUNK: u64 = 0x7fff_f000;
+
+    let mut position = offset as i64;
+    let mut sent = 0;
+
+    while sent < length {
+        let count = (length - sent).min(MAX_CHUNK) as usize;
+        let result =
+            unsafe { sendfile(stream.as_raw_fd(), file.as_raw_fd(), &mut position, count) };
+
+        if result < 0 {
+            let error = io::Error::last_os_error();
+            match error.kind() {
+                io::ErrorKind::Interrupted => continue,
+                io::ErrorKind::InvalidInput | io::ErrorKind::Unsupported => break,
+                _ => return Err(error),
+            }
+        }
+
+        if result == 0 {
+            break;
+        }
+
+        sent += result as u64;
+    }
+
+    Ok(sent)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn sendfile(_stream: &TcpStream, _file: &File, _offset: u64, _length: u64) -> io::Result<u64> {
+    return Ok(0);
+}
+
+#[cfg(test)]
+#[path = "./body_test.rs"]
+mod test;
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod tests {
+    use std::fs::{self, File};
+
+    use super::super::{write_stream, Body};
+
+    static PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+
+    #[test]
+    fn test_file_body_len() {
+        let body = Body::file(File::open(PATH).unwrap()).unwrap();
+        assert_eq!(body.size(), Some(fs::metadata(PATH).unwrap().len()));
+        assert!(body.as_bytes().is_none());
+    }
+
+    #[test]
+    fn test_file_body_slice() {
+        let expected = fs::read(PATH).unwrap();
+        let body = Body::file(File::open(PATH).unwrap()).unwrap();
+        let body = body.slice(2, 6).unwrap();
+        assert_eq!(body.size(), Some(5));
+        assert_eq!(body.into_bytes().unwrap(), &expected[2..=6]);
+    }
+
+    #[test]
+    fn test_bytes_body_read_range() {
+        let body = Body::Bytes(b"0123456789".to_vec());
+        assert_eq!(body.read_range(3, 5).unwrap(), b"345");
+    }
+
+    #[test]
+    fn test_write_chunked() {
+        let mut out = Vec::new();
+        let chunks = vec![b"Hello".to_vec(), Vec::new(), b", world!".to_vec()];
+        match Body::stream(chunks.into_iter()) {
+            Body::Stream(mut chunks) => {
+                write_stream(&mut out, &mut chunks, None, Some(&[])).unwrap()
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(out, b"5\r\nHello\r\n8\r\n, world!\r\n0\r\n\r\n");
+    }
+
+    #[test]
+    fn test_write_chunked_trailers() {
+        let mut out = Vec::new();
+        let trailers = vec!["X-Rows: 2".to_string()];
+        match Body::stream(vec![b"a".to_vec()].into_iter()) {
+            Body::Stream(mut chunks) => {
+                write_stream(&mut out, &mut chunks, None, Some(&trailers)).unwrap()
+            }
+            _ => unreachable!(),
+        }
+        assert_eq!(out, b"1\r\na\r\n0\r\nX-Rows: 2\r\n\r\n");
+    }
+
+    #[test]
+    fn test_reader_body() {
+        let body = Body::reader(std::io::Cursor::new(b"streamed".to_vec()));
+        assert_eq!(body.size(), None);
+        assert_eq!(body.into_bytes().unwrap(), b"streamed");
+    }
+}
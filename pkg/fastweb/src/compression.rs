@@ -0,0 +1,239 @@
+use std::io::{self, Read, Write};
+
+use flate2::{
+    read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder},
+    write::{GzEncoder, ZlibEncoder},
+    Compression,
+};
+
+use crate::{
+    body::Body,
+    http::{Encoding, Headers, HttpStatus},
+    response::Response,
+};
+
+/// Decides which responses are worth compressing.
+#[derive(Clone)]
+pub struct CompressionPolicy {
+    min_size: u64,
+    mime_types: Vec<String>,
+    level: u32,
+}
+
+impl Default for CompressionPolicy {
+    fn default() -> Self {
+        CompressionPolicy {
+            min_size: 1024,
+            mime_types: vec![
+                "text/*".to_string(),
+                "application/json".to_string(),
+                "application/javascript".to_string(),
+                "application/xml".to_string(),
+                "application/wasm".to_string(),
+                "image/svg+xml".to_string(),
+            ],
+            level: Compression::default().level(),
+        }
+    }
+}
+
+impl CompressionPolicy {
+    pub fn new() -> CompressionPolicy {
+        CompressionPolicy::default()
+    }
+
+    /// Bodies smaller than this many bytes are sent uncompressed.
+    pub fn min_size(mut self, min_size: u64) -> CompressionPolicy {
+        self.min_size = min_size;
+        self
+    }
+
+    /// MIME types eligible for compression, either exact (`application/json`)
+    /// or a whole top-level type (`text/*`).
+    pub fn mime_types(mut self, mime_types: &[&str]) -> CompressionPolicy {
+        self.mime_types = mime_types.iter().map(|m| m.to_ascii_lowercase()).collect();
+        self
+    }
+
+    /// Compression level from 0 (none) to 9 (best).
+    pub fn level(mut self, level: u32) -> CompressionPolicy {
+        assert!(level <= 9, "Compression level must be between 0 and 9");
+        self.level = level;
+        self
+    }
+
+    pub fn get_level(&self) -> u32 {
+        self.level
+    }
+
+    fn allows_mime(&self, response: &Response) -> bool {
+        let mime = response.content_type.mime();
+
+        self.mime_types
+            .iter()
+            .any(|allowed| match allowed.split_once("/*") {
+                Some((type_, "")) => type_ == mime.type_(),
+                _ => *allowed == mime.essence(),
+            })
+    }
+
+    /// Whether the response would be compressed for a client that accepts it.
+    pub fn is_eligible(&self, response: &Response) -> bool {
+        if !response.is_compressible() || response.header("Content-Encoding").is_some() {
+            return false;
+        }
+
+        // Partial and empty responses, and file bodies sent with sendfile, are left as is
+        let status = response.status().to_code();
+        if status == HttpStatus::StatusPartialContent.to_code()
+            || status == HttpStatus::StatusNotModified.to_code()
+            || matches!(response.content, Body::File(_))
+        {
+            return false;
+        }
+
+        if response
+            .content
+            .size()
+            .is_some_and(|size| size < self.min_size)
+        {
+            return false;
+        }
+
+        self.allows_mime(response)
+    }
+
+    /// Applies the policy: compresses eligible responses with `encoding` and
+    /// marks them as varying by `Accept-Encoding`.
+    pub fn apply(&self, response: &mut Response, encoding: &Option<Encoding>) {
+        if !self.is_eligible(response) {
+            return;
+        }
+
+        let vary = response.header(Headers::Vary.to_str()).unwrap_or_default();
+        if !vary.split(',').any(|v| {
+            v.trim()
+                .eq_ignore_ascii_case(Headers::AcceptEncoding.to_str())
+        }) {
+            response.set_header(Headers::Vary.to_str(), Headers::AcceptEncoding.to_str());
+        }
+
+        response.set_compression_level(self.level);
+        response.set_encoding(encoding);
+    }
+}
+
+/// Incrementally compresses content with one of the supported content codings.
+pub enum Encoder {
+    Gzip(GzEncoder<Vec<u8>>),
+    // The "deflate" content coding is the zlib format (RFC 9110 section 8.4.1.2)
+    Deflate(ZlibEncoder<Vec<u8>>),
+}
+
+impl Encoder {
+    /// Returns `None` for the identity coding.
+    pub fn new(encoding: &Encoding, level: u32) -> Option<Encoder> {
+        let level = Compression::new(level);
+
+        match encoding {
+            Encoding::GZIP => Some(Encoder::Gzip(GzEncoder::new(Vec::new(), level))),
+            Encoding::DEFLATE => Some(Encoder::Deflate(ZlibEncoder::new(Vec::new(), level))),
+            Encoding::Identity => None,
+        }
+    }
+
+    pub fn write(&mut self, data: &[u8]) -> io::Result<()> {
+        match self {
+            Encoder::Gzip(encoder) => encoder.write_all(data),
+            Encoder::Deflate(encoder) => encoder.write_all(data),
+        }
+    }
+
+    /// Takes the compressed output produced so far.
+    pub fn take(&mut self) -> Vec<u8> {
+        match self {
+            Encoder::Gzip(encoder) => std::mem::take(encoder.get_mut()),
+            Encoder::Deflate(encoder) => std::mem::take(encoder.get_mut()),
+        }
+    }
+
+    /// Completes the stream and returns the remaining compressed output.
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        match self {
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Deflate(encoder) => encoder.finish(),
+        }
+    }
+}
+
+/// Compresses `data` in one go, returning it unchanged for the identity coding.
+pub fn compress(encoding: &Encoding, level: u32, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut encoder = match Encoder::new(encoding, level) {
+        Some(encoder) => encoder,
+        None => return Ok(data.to_vec()),
+    };
+
+    encoder.write(data)?;
+    let mut compressed = encoder.take();
+    compressed.extend(encoder.finish()?);
+
+    Ok(compressed)
+}
+
+#[derive(Debug)]
+pub enum DecompressError {
+    /// A content coding the server cannot decode.
+    Unsupported(String),
+    /// The decoded content exceeds the configured limit.
+    TooLarge,
+    Invalid(io::Error),
+}
+
+// Reads at most `limit` bytes of decoded content.
+fn read_limited<R: Read>(reader: R, limit: u64) -> Result<Vec<u8>, DecompressError> {
+    let mut decoded = Vec::new();
+
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut decoded)
+        .map_err(DecompressError::Invalid)?;
+
+    if decoded.len() as u64 > limit {
+        return Err(DecompressError::TooLarge);
+    }
+
+    Ok(decoded)
+}
+
+/// Decodes content sent with the given `Content-Encoding` header value,
+/// producing at most `limit` bytes.
+pub fn decompress(
+    content_encoding: &str,
+    data: Vec<u8>,
+    limit: u64,
+) -> Result<Vec<u8>, DecompressError> {
+    let mut data = data;
+
+    // Codings are listed in the order they were applied, so undo them in reverse
+    for coding in content_encoding.split(',').map(|c| c.trim()).rev() {
+        data = match Encoding::from_str(coding) {
+            Some(Encoding::Identity) => continue,
+            Some(Encoding::GZIP) => read_limited(MultiGzDecoder::new(&data[..]), limit)?,
+            // Some clients send raw deflate data instead of the zlib format
+            Some(Encoding::DEFLATE) => match read_limited(ZlibDecoder::new(&data[..]), limit) {
+                Err(DecompressError::Invalid(_)) => {
+                    read_limited(DeflateDecoder::new(&data[..]), limit)?
+                }
+                result => result?,
+            },
+            None if coding.is_empty() => continue,
+            None => return Err(DecompressError::Unsupported(coding.to_string())),
+        };
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+#[path = "./compression_test.rs"]
+mod test;
@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{compress, CompressionPolicy};
+    use crate::http::{Encoding, HttpContentType, HttpStatus};
+    use crate::response;
+
+    fn large_text() -> String {
+        "fastweb ".repeat(256)
+    }
+
+    #[test]
+    fn test_compress_deflate() {
+        let compressed = compress(&Encoding::DEFLATE, 6, large_text().as_bytes()).unwrap();
+        assert!(compressed.len() < large_text().len());
+        assert_eq!(compressed[0], 0x78);
+    }
+
+    #[test]
+    fn test_policy_min_size() {
+        let policy = CompressionPolicy::new();
+        let mut small = response::text(HttpStatus::StatusOK, "pong".to_string());
+        policy.apply(&mut small, &Some(Encoding::GZIP));
+        assert!(small.header("Content-Encoding").is_none());
+        assert!(small.header("Vary").is_none());
+
+        let mut large = response::text(HttpStatus::StatusOK, large_text());
+        policy.apply(&mut large, &Some(Encoding::GZIP));
+        assert_eq!(large.header("Content-Encoding"), Some("gzip"));
+        assert_eq!(large.header("Vary"), Some("Accept-Encoding"));
+    }
+
+    #[test]
+    fn test_policy_vary_without_encoding() {
+        let mut response = response::text(HttpStatus::StatusOK, large_text());
+        CompressionPolicy::new().apply(&mut response, &None);
+        assert!(response.header("Content-Encoding").is_none());
+        assert_eq!(response.header("Vary"), Some("Accept-Encoding"));
+    }
+
+    #[test]
+    fn test_policy_mime_types() {
+        let content = large_text().into_bytes();
+        let mut image = response::bytes(HttpStatus::StatusOK, content, "image/png".into());
+        CompressionPolicy::new().apply(&mut image, &Some(Encoding::GZIP));
+        assert!(image.header("Content-Encoding").is_none());
+
+        let policy = CompressionPolicy::new().mime_types(&["application/json"]);
+        let mut html = response::new(HttpStatus::StatusOK, large_text(), HttpContentType::HTML);
+        policy.apply(&mut html, &Some(Encoding::GZIP));
+        assert!(html.header("Content-Encoding").is_none());
+    }
+
+    #[test]
+    fn test_policy_opt_out() {
+        let mut response = response::text(HttpStatus::StatusOK, large_text());
+        response.set_compressible(false);
+        CompressionPolicy::new().apply(&mut response, &Some(Encoding::GZIP));
+        assert!(response.header("Content-Encoding").is_none());
+    }
+}
@@ -0,0 +1,197 @@
+use std::time::SystemTime;
+
+use logger::debug;
+
+use crate::{
+    body::Body,
+    http::{date, Headers, HttpContentType, HttpMethod, HttpStatus},
+    request::Request,
+    response::{self, Response},
+};
+
+/// Computes an entity tag from the content, e.g. `"1a-9f0e4c2b7d31a865"`, or
+/// `W/"1a-9f0e4c2b7d31a865"` when `weak` is set.
+pub fn etag(content: &[u8], weak: bool) -> String {
+    // 64 bit FNV-1a, stable across builds unlike the std hasher
+    let hash = content.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+
+    let tag = format!("\"{:x}-{:016x}\"", content.len(), hash);
+
+    if weak {
+        return format!("W/{}", tag);
+    }
+
+    tag
+}
+
+// Splits a list of entity tags, keeping the W/ prefix of weak ones. Tags may
+// contain commas, so the list cannot simply be split on them.
+fn parse_tags(value: &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    let mut rest = value.trim_start_matches([' ', '\t', ',']);
+
+    while !rest.is_empty() {
+        let start = if rest.starts_with("W/") { 2 } else { 0 };
+        let end = match rest[start..].strip_prefix('"') {
+            Some(quoted) => match quoted.find('"') {
+                Some(close) => start + close + 2,
+                None => rest.len(),
+            },
+            None => rest.find(',').unwrap_or(rest.len()),
+        };
+
+        tags.push(rest[..end].trim());
+        rest = rest[end..].trim_start_matches([' ', '\t', ',']);
+    }
+
+    tags
+}
+
+fn is_weak(tag: &str) -> bool {
+    tag.starts_with("W/")
+}
+
+// Strong comparison (RFC 9110 section 8.8.3.2): both tags strong and equal.
+fn strong_match(a: &str, b: &str) -> bool {
+    !is_weak(a) && !is_weak(b) && a == b
+}
+
+// Weak comparison: equal once the weakness indicators are ignored.
+fn weak_match(a: &str, b: &str) -> bool {
+    a.trim_start_matches("W/") == b.trim_start_matches("W/")
+}
+
+/// The validators of the current representation of a resource.
+#[derive(Debug, Clone, Default)]
+pub struct Validators {
+    pub etag: Option<String>,
+    pub last_modified: Option<SystemTime>,
+}
+
+impl Validators {
+    /// Reads the `ETag` and `Last-Modified` headers of a response.
+    pub fn from_response(response: &Response) -> Validators {
+        Validators {
+            etag: response.header(Headers::ETag.to_str()).map(String::from),
+            last_modified: response
+                .header(Headers::LastModified.to_str())
+                .and_then(date::parse),
+        }
+    }
+}
+
+/// The conditional headers of a request, kept so they can be evaluated once
+/// the handler has produced a response.
+#[derive(Debug, Clone)]
+pub struct Preconditions {
+    method: HttpMethod,
+    if_match: Option<String>,
+    if_none_match: Option<String>,
+    if_modified_since: Option<SystemTime>,
+    if_unmodified_since: Option<SystemTime>,
+}
+
+impl Preconditions {
+    pub fn from_request(request: &Request) -> Preconditions {
+        let header = |name: Headers| request.header(name.to_str()).map(String::from);
+        // Invalid dates are ignored (RFC 9110 sections 13.1.3 and 13.1.4)
+        let date = |name: Headers| request.header(name.to_str()).and_then(date::parse);
+
+        Preconditions {
+            method: request.method().clone(),
+            if_match: header(Headers::IfMatch),
+            if_none_match: header(Headers::IfNoneMatch),
+            if_modified_since: date(Headers::IfModifiedSince),
+            if_unmodified_since: date(Headers::IfUnmodifiedSince),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.if_match.is_none()
+            && self.if_none_match.is_none()
+            && self.if_modified_since.is_none()
+            && self.if_unmodified_since.is_none()
+    }
+
+    fn is_safe(&self) -> bool {
+        matches!(self.method, HttpMethod::GET | HttpMethod::HEAD)
+    }
+
+    /// Evaluates the preconditions against the current representation, `None`
+    /// if the resource does not exist, in the order of RFC 9110 section 13.2.2.
+    /// Returns the status to answer with instead, if any.
+    pub fn evaluate(&self, current: Option<&Validators>) -> Option<HttpStatus> {
+        let etag = current.and_then(|current| current.etag.as_deref());
+        let last_modified = current
+            .and_then(|current| current.last_modified)
+            .map(date::truncate);
+
+        if let Some(if_match) = &self.if_match {
+            let matched = current.is_some()
+                && parse_tags(if_match)
+                    .into_iter()
+                    .any(|tag| tag == "*" || etag.is_some_and(|etag| strong_match(tag, etag)));
+
+            if !matched {
+                return Some(HttpStatus::StatusPreconditionFailed);
+            }
+        } else if let (Some(since), Some(modified)) = (self.if_unmodified_since, last_modified) {
+            if modified > since {
+                return Some(HttpStatus::StatusPreconditionFailed);
+            }
+        }
+
+        if let Some(if_none_match) = &self.if_none_match {
+            let matched = current.is_some()
+                && parse_tags(if_none_match)
+                    .into_iter()
+                    .any(|tag| tag == "*" || etag.is_some_and(|etag| weak_match(tag, etag)));
+
+            if matched && self.is_safe() {
+                return Some(HttpStatus::StatusNotModified);
+            }
+            if matched {
+                return Some(HttpStatus::StatusPreconditionFailed);
+            }
+        } else if let (Some(since), Some(modified), true) =
+            (self.if_modified_since, last_modified, self.is_safe())
+        {
+            if modified <= since {
+                return Some(HttpStatus::StatusNotModified);
+            }
+        }
+
+        None
+    }
+
+    /// Turns a successful response to a `GET` or `HEAD` into a 304 or 412 if
+    /// the preconditions fail against its `ETag` and `Last-Modified` headers.
+    /// Other methods have already acted on the resource by now, their handlers
+    /// must use [`check`] first.
+    pub fn apply(&self, response: &mut Response) {
+        if self.is_empty() || !self.is_safe() || !(200..300).contains(&response.status().to_code())
+        {
+            return;
+        }
+
+        if let Some(status) = self.evaluate(Some(&Validators::from_response(response))) {
+            debug!("Precondition evaluated to {}", status);
+            response.status = status;
+            response.content = Body::Bytes(Vec::new());
+        }
+    }
+}
+
+/// Evaluates the request's preconditions in a handler, before acting on the
+/// resource, e.g. to reject a `PUT` whose `If-Match` no longer matches.
+/// `current` is `None` if the resource does not exist yet.
+pub fn check(request: &Request, current: Option<&Validators>) -> Option<Response> {
+    let status = Preconditions::from_request(request).evaluate(current)?;
+    Some(response::new(status, String::new(), HttpContentType::TEXT))
+}
+
+#[cfg(test)]
+#[path = "./conditional_test.rs"]
+mod test;
@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::super::{etag, parse_tags, Preconditions, Validators};
+    use crate::http::HttpStatus;
+    use crate::request;
+    use crate::response;
+
+    fn preconditions(method: &str, headers: &[(&str, &str)]) -> Preconditions {
+        let headers = headers
+            .iter()
+            .map(|(name, value)| format!("{}: {}\r\n", name, value))
+            .collect::<String>();
+        let request = request::parse(&format!("{} / HTTP/1.1\r\n{}\r\n", method, headers));
+        Preconditions::from_request(&request)
+    }
+
+    fn current() -> Validators {
+        Validators {
+            etag: Some("\"v2\"".to_string()),
+            // Tue, 15 Nov 1994 08:12:31 GMT
+            last_modified: Some(UNIX_EPOCH + Duration::from_secs(784887151)),
+        }
+    }
+
+    fn status(preconditions: Preconditions, current: Option<&Validators>) -> Option<u32> {
+        preconditions
+            .evaluate(current)
+            .map(|status| status.to_code())
+    }
+
+    #[test]
+    fn test_etag() {
+        assert_eq!(etag(b"hello", false), etag(b"hello", false));
+        assert_ne!(etag(b"hello", false), etag(b"hellp", false));
+        assert!(etag(b"hello", true).starts_with("W/\"5-"));
+    }
+
+    #[test]
+    fn test_parse_tags() {
+        assert_eq!(
+            parse_tags("\"a,b\", W/\"c\",*"),
+            vec!["\"a,b\"", "W/\"c\"", "*"]
+        );
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let get = preconditions("GET", &[("If-None-Match", "\"v1\", W/\"v2\"")]);
+        assert_eq!(status(get, Some(&current())), Some(304));
+
+        let put = preconditions("PUT", &[("If-None-Match", "*")]);
+        assert_eq!(status(put.clone(), Some(&current())), Some(412));
+        assert_eq!(status(put, None), None);
+    }
+
+    #[test]
+    fn test_if_match_is_strong() {
+        let put = preconditions("PUT", &[("If-Match", "\"v2\"")]);
+        assert_eq!(status(put, Some(&current())), None);
+
+        let put = preconditions("PUT", &[("If-Match", "W/\"v2\"")]);
+        assert_eq!(status(put, Some(&current())), Some(412));
+
+        let put = preconditions("PUT", &[("If-Match", "*")]);
+        assert_eq!(status(put, None), Some(412));
+    }
+
+    #[test]
+    fn test_dates() {
+        let before = "Tue, 15 Nov 1994 08:12:30 GMT";
+        let after = "Tue, 15 Nov 1994 08:12:31 GMT";
+
+        let get = preconditions("GET", &[("If-Modified-Since", after)]);
+        assert_eq!(status(get, Some(&current())), Some(304));
+        let get = preconditions("GET", &[("If-Modified-Since", before)]);
+        assert_eq!(status(get, Some(&current())), None);
+
+        let put = preconditions("PUT", &[("If-Unmodified-Since", before)]);
+        assert_eq!(status(put, Some(&current())), Some(412));
+    }
+
+    #[test]
+    fn test_if_none_match_takes_precedence() {
+        let get = preconditions(
+            "GET",
+            &[
+                ("If-None-Match", "\"v1\""),
+                ("If-Modified-Since", "Tue, 15 Nov 1994 08:12:31 GMT"),
+            ],
+        );
+        assert_eq!(status(get, Some(&current())), None);
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut response = response::text(HttpStatus::StatusOK, "hello".to_string());
+        response.set_etag(false);
+        let etag = response.header("ETag").unwrap().to_string();
+
+        preconditions("GET", &[("If-None-Match", &etag)]).apply(&mut response);
+        assert_eq!(response.status().to_code(), 304);
+
+        let raw = String::from_utf8(response.build()).unwrap();
+        assert!(raw.contains(&format!("ETag: {}", etag)));
+        assert!(!raw.contains("Content-Length"));
+        assert!(raw.ends_with("\r\n\r\n"));
+    }
+}
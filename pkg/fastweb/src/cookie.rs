@@ -0,0 +1,218 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    time::{Duration, SystemTime},
+};
+
+use crate::{
+    http::{date, mime},
+    url,
+};
+
+/// Controls whether a cookie is sent with cross-site requests.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+
+impl SameSite {
+    pub fn to_str(&self) -> &str {
+        match self {
+            SameSite::Strict => "Strict",
+            SameSite::Lax => "Lax",
+            SameSite::None => "None",
+        }
+    }
+}
+
+// Characters allowed in a cookie value (RFC 6265 section 4.1.1): visible
+// ASCII except double quotes, commas, semicolons and backslashes.
+fn is_cookie_octet(byte: u8) -> bool {
+    matches!(byte, 0x21 | 0x23..=0x2B | 0x2D..=0x3A | 0x3C..=0x5B | 0x5D..=0x7E)
+}
+
+// Percent-encodes the bytes of a value outside of the cookie octets, and `%`
+// itself so that encoded values can be told apart from plain ones.
+fn encode_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        if is_cookie_octet(byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+// Checks an attribute value, which may not hold control characters or end
+// the attribute early with a semicolon.
+fn check_attribute(name: &str, value: &str) -> Result<(), String> {
+    if value.bytes().any(|b| b.is_ascii_control() || b == b';') {
+        return Err(format!("Invalid cookie {} '{}'", name, value));
+    }
+
+    Ok(())
+}
+
+/// A cookie to set on the client, formatted as a `Set-Cookie` value
+/// (RFC 6265 section 4.1). Bytes of the value outside of the cookie octets,
+/// e.g. spaces, commas or semicolons, and `%` are percent-encoded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    expires: Option<SystemTime>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Returns an error if `name` is not a token, e.g. empty or holding `=`.
+    pub fn new(name: &str, value: &str) -> Result<Cookie, String> {
+        if !mime::is_token(name) {
+            return Err(format!("Invalid cookie name '{}'", name));
+        }
+
+        Ok(Cookie {
+            name: name.to_string(),
+            value: encode_value(value),
+            path: None,
+            domain: None,
+            max_age: None,
+            expires: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        })
+    }
+
+    /// A cookie that makes the client delete the cookie called `name`.
+    pub fn removal(name: &str) -> Result<Cookie, String> {
+        Ok(Cookie::new(name, "")?
+            .max_age(Duration::ZERO)
+            .expires(SystemTime::UNIX_EPOCH))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The value as sent, percent-encoded where needed.
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns an error if `path` holds control characters or `;`.
+    pub fn path(mut self, path: &str) -> Result<Cookie, String> {
+        check_attribute("path", path)?;
+        self.path = Some(path.to_string());
+        Ok(self)
+    }
+
+    /// Returns an error if `domain` holds control characters or `;`.
+    pub fn domain(mut self, domain: &str) -> Result<Cookie, String> {
+        check_attribute("domain", domain)?;
+        self.domain = Some(domain.to_string());
+        Ok(self)
+    }
+
+    pub fn max_age(mut self, max_age: Duration) -> Cookie {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    pub fn expires(mut self, expires: SystemTime) -> Cookie {
+        self.expires = Some(expires);
+        self
+    }
+
+    pub fn secure(mut self, secure: bool) -> Cookie {
+        self.secure = secure;
+        self
+    }
+
+    pub fn http_only(mut self, http_only: bool) -> Cookie {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Sets the `SameSite` attribute. `SameSite::None` also makes the cookie
+    /// secure, as browsers reject it otherwise.
+    pub fn same_site(mut self, same_site: SameSite) -> Cookie {
+        if same_site == SameSite::None {
+            self.secure = true;
+        }
+        self.same_site = Some(same_site);
+        self
+    }
+}
+
+impl Display for Cookie {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(max_age) = &self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if let Some(expires) = &self.expires {
+            write!(f, "; Expires={}", date::format(*expires))?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = &self.same_site {
+            write!(f, "; SameSite={}", same_site.to_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Parses a `Cookie` request header into names and values, percent-decoding
+/// the values. When a name is repeated the first value wins, as clients send
+/// the most specific path first.
+pub fn parse(header: &str) -> HashMap<String, String> {
+    let mut cookies = HashMap::new();
+
+    for pair in header.split(';').map(|pair| pair.trim()) {
+        let (name, value) = match pair.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+            _ => continue,
+        };
+
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+
+        // Values set by other means may hold a stray `%` or encoded bytes that
+        // are not UTF-8, those are kept as sent
+        cookies
+            .entry(name.to_string())
+            .or_insert_with(|| url::decode(value).unwrap_or_else(|| value.to_string()));
+    }
+
+    cookies
+}
+
+#[cfg(test)]
+#[path = "./cookie_test.rs"]
+mod test;
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::super::{parse, Cookie, SameSite};
+    use crate::http::HttpStatus;
+    use crate::{request, response};
+
+    #[test]
+    fn test_parse() {
+        let cookies = parse("session=abc123; theme=\"dark\"; invalid; session=other");
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies["session"], "abc123");
+        assert_eq!(cookies["theme"], "dark");
+    }
+
+    #[test]
+    fn test_request_cookies() {
+        let request = request::parse("GET / HTTP/1.1\r\ncookie: a=1; b=2\r\n\r\n");
+        assert_eq!(request.cookie("b"), Some("2".to_string()));
+        assert!(request.cookie("c").is_none());
+    }
+
+    #[test]
+    fn test_format() {
+        let cookie = Cookie::new("session", "abc123")
+            .unwrap()
+            .path("/")
+            .unwrap()
+            .domain("example.com")
+            .unwrap()
+            .max_age(Duration::from_secs(3600))
+            .expires(UNIX_EPOCH + Duration::from_secs(784111777))
+            .http_only(true)
+            .same_site(SameSite::Lax);
+
+        assert_eq!(
+            cookie.to_string(),
+            "session=abc123; Path=/; Domain=example.com; Max-Age=3600; \
+             Expires=Sun, 06 Nov 1994 08:49:37 GMT; HttpOnly; SameSite=Lax"
+        );
+    }
+
+    #[test]
+    fn test_value_encoding() {
+        let cookie = Cookie::new("note", "a b;c,d\"e\\f%").unwrap();
+        assert_eq!(cookie.value(), "a%20b%3Bc%2Cd%22e%5Cf%25");
+        assert_eq!(cookie.to_string(), "note=a%20b%3Bc%2Cd%22e%5Cf%25");
+    }
+
+    #[test]
+    fn test_value_roundtrip() {
+        for value in ["a b;c", "100%", "%20", "café", "x=1&y=2"] {
+            let cookie = Cookie::new("v", value).unwrap();
+            assert_eq!(parse(&cookie.to_string())["v"], value);
+        }
+    }
+
+    #[test]
+    fn test_parse_decodes_values() {
+        let cookies = parse("a=hello%20world; b=50%; c=%FF");
+        assert_eq!(cookies["a"], "hello world");
+        assert_eq!(cookies["b"], "50%");
+        assert_eq!(cookies["c"], "%FF");
+    }
+
+    #[test]
+    fn test_invalid_name() {
+        assert_eq!(
+            Cookie::new("a=b", "1").unwrap_err(),
+            "Invalid cookie name 'a=b'"
+        );
+        assert!(Cookie::removal("").is_err());
+    }
+
+    #[test]
+    fn test_invalid_path() {
+        let cookie = Cookie::new("id", "1").unwrap();
+        assert_eq!(
+            cookie.path("/; Domain=evil.com").unwrap_err(),
+            "Invalid cookie path '/; Domain=evil.com'"
+        );
+    }
+
+    #[test]
+    fn test_invalid_domain() {
+        let cookie = Cookie::new("id", "1").unwrap();
+        assert_eq!(
+            cookie.domain("example.com\r\nX-Injected: 1").unwrap_err(),
+            "Invalid cookie domain 'example.com\r\nX-Injected: 1'"
+        );
+    }
+
+    #[test]
+    fn test_same_site_none_is_secure() {
+        let cookie = Cookie::new("id", "1").unwrap().same_site(SameSite::None);
+        assert_eq!(cookie.to_string(), "id=1; Secure; SameSite=None");
+    }
+
+    #[test]
+    fn test_set_cookie() {
+        let mut response = response::text(HttpStatus::StatusOK, String::new());
+        response.set_cookie(&Cookie::new("a", "1").unwrap());
+        response.set_cookie(&Cookie::removal("b").unwrap());
+
+        let raw = String::from_utf8(response.build()).unwrap();
+        assert!(raw.contains("Set-Cookie: a=1\r\n"));
+        assert!(
+            raw.contains("Set-Cookie: b=; Max-Age=0; Expires=Thu, 01 Jan 1970 00:00:00 GMT\r\n")
+        );
+    }
+}
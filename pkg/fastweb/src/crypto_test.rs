@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{
+        apply_keystream, constant_time_eq, from_hex, hmac_sha256, random_bytes, sha256, to_hex,
+    };
+
+    #[test]
+    fn test_sha256() {
+        assert_eq!(
+            to_hex(&sha256(b"")),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            to_hex(&sha256(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+        );
+    }
+
+    #[test]
+    fn test_hmac_sha256() {
+        // RFC 4231 test cases 2 and 6
+        assert_eq!(
+            to_hex(&hmac_sha256(b"Jefe", b"what do ya want for nothing?")),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+        assert_eq!(
+            to_hex(&hmac_sha256(
+                &[0xaa; 131],
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )),
+            "60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54"
+        );
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"ab"));
+    }
+
+    #[test]
+    fn test_random_bytes() {
+        assert_eq!(random_bytes(20).len(), 20);
+        assert_ne!(random_bytes(16), random_bytes(16));
+    }
+
+    #[test]
+    fn test_from_hex() {
+        assert_eq!(from_hex("00ff7A"), Some(vec![0x00, 0xff, 0x7a]));
+        assert_eq!(from_hex(&to_hex(b"session")), Some(b"session".to_vec()));
+        assert!(from_hex("abc").is_none());
+        assert!(from_hex("zz").is_none());
+        assert!(from_hex("\u{e9}a").is_none());
+    }
+
+    #[test]
+    fn test_apply_keystream() {
+        let plaintext = b"a message longer than one 32 byte block".to_vec();
+
+        let mut data = plaintext.clone();
+        apply_keystream(b"key", b"nonce", &mut data);
+        assert_ne!(data, plaintext);
+
+        let mut other = plaintext.clone();
+        apply_keystream(b"key", b"other nonce", &mut other);
+        assert_ne!(other, data);
+
+        apply_keystream(b"key", b"nonce", &mut data);
+        assert_eq!(data, plaintext);
+    }
+}
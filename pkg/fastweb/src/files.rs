@@ -0,0 +1,116 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use logger::debug;
+
+use crate::{
+    body::Body,
+    conditional::{self, Validators},
+    http::{date, Headers, HttpContentType, HttpStatus},
+    request::Request,
+    response::{self, Response},
+    router::not_found,
+    url, EMPTY, PATH_SEPARATOR,
+};
+
+/// Name of the path parameter holding the requested file path.
+pub static PATH_PARAM: &str = "path";
+
+static INDEX_FILE: &str = "index.html";
+
+// Resolves the requested path inside `root`, refusing anything that escapes it.
+fn resolve(root: &Path, requested: &str) -> Option<PathBuf> {
+    let decoded = url::decode(requested)?;
+
+    if decoded.contains('\0') || decoded.contains('\\') {
+        return None;
+    }
+
+    let relative = url::normalize(&decoded);
+    let mut path = root.join(relative.trim_start_matches(PATH_SEPARATOR));
+
+    if path.is_dir() {
+        path = path.join(INDEX_FILE);
+    }
+
+    // Symlinks could still point outside the root, so compare canonical paths.
+    let canonical_root = root.canonicalize().ok()?;
+    let canonical_path = path.canonicalize().ok()?;
+
+    if !canonical_path.starts_with(&canonical_root) || !canonical_path.is_file() {
+        return None;
+    }
+
+    Some(canonical_path)
+}
+
+fn etag(length: u64, modified: SystemTime) -> String {
+    let secs = modified
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    format!("\"{:x}-{:x}\"", length, secs)
+}
+
+/// Serves the file named by the request's `path` parameter from `root`.
+pub fn serve(request: &Request, root: &Path) -> Response {
+    let requested = request
+        .path_params()
+        .get(PATH_PARAM)
+        .map(|path| path.as_str())
+        .unwrap_or_default();
+
+    let path = match resolve(root, requested) {
+        Some(path) => path,
+        None => return not_found(),
+    };
+
+    let metadata = match fs::metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(_) => return not_found(),
+    };
+
+    let modified = date::truncate(metadata.modified().unwrap_or(UNIX_EPOCH));
+    let etag = etag(metadata.len(), modified);
+    let last_modified = date::format(modified);
+    let content_type = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(HttpContentType::from_extension)
+        .unwrap_or(HttpContentType::from_extension(EMPTY));
+
+    let validators = Validators {
+        etag: Some(etag.clone()),
+        last_modified: Some(modified),
+    };
+
+    // Answer 304 and 412 without opening the file
+    let mut response = match conditional::check(request, Some(&validators)) {
+        Some(response) => {
+            debug!("File precondition failed: {:?}", path);
+            response
+        }
+        None => {
+            debug!("Serving file: {:?}", path);
+            let mut response = response::new(HttpStatus::StatusOK, String::new(), content_type);
+            response.content = match File::open(&path).and_then(Body::file) {
+                Ok(content) => content,
+                Err(_) => return not_found(),
+            };
+            response
+        }
+    };
+
+    response.set_header(Headers::AcceptRanges.to_str(), "bytes");
+    response.set_header(Headers::ETag.to_str(), &etag);
+    response.set_header(Headers::LastModified.to_str(), &last_modified);
+
+    response
+}
+
+#[cfg(test)]
+#[path = "./files_test.rs"]
+mod test;
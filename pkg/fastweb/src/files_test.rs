@@ -0,0 +1,28 @@
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::super::resolve;
+
+    fn root() -> &'static Path {
+        Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/src"))
+    }
+
+    #[test]
+    fn test_resolve_file() {
+        let path = resolve(root(), "files.rs").unwrap();
+        assert!(path.ends_with("files.rs"));
+    }
+
+    #[test]
+    fn test_resolve_traversal() {
+        assert!(resolve(root(), "../Cargo.toml").is_none());
+        assert!(resolve(root(), "%2e%2e/Cargo.toml").is_none());
+        assert!(resolve(root(), "..%2FCargo.toml").is_none());
+    }
+
+    #[test]
+    fn test_resolve_missing() {
+        assert!(resolve(root(), "missing.rs").is_none());
+    }
+}
@@ -0,0 +1,140 @@
+use badserde::json::Serde;
+
+use crate::{
+    http::HttpStatus,
+    request::Request,
+    response::{self, Response},
+    url,
+};
+
+pub static URLENCODED: &str = "application/x-www-form-urlencoded";
+
+/// The fields of a submitted form, in the order they were sent. A name may
+/// appear several times, e.g. for checkboxes or multiple selects.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FormData {
+    fields: Vec<(String, String)>,
+}
+
+impl FormData {
+    pub fn new(fields: Vec<(String, String)>) -> FormData {
+        FormData { fields }
+    }
+
+    /// Returns the first value of the field.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns every value of the field.
+    pub fn get_all(&self, name: &str) -> Vec<&str> {
+        self.fields
+            .iter()
+            .filter(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Formats the fields as a JSON object for `badserde`. Every value is a
+    /// string, fields sent more than once become arrays of strings.
+    pub fn to_json(&self) -> String {
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in &self.fields {
+            if !names.contains(&name.as_str()) {
+                names.push(name);
+            }
+        }
+
+        let members = names
+            .iter()
+            .map(|name| {
+                let values = self.get_all(name);
+                let value = match values.as_slice() {
+                    [value] => value.to_string().to_json(),
+                    values => values
+                        .iter()
+                        .map(|value| value.to_string())
+                        .collect::<Vec<String>>()
+                        .to_json(),
+                };
+                format!("{}: {}", name.to_string().to_json(), value)
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+
+        format!("{{{}}}", members)
+    }
+}
+
+/// Parses an `application/x-www-form-urlencoded` request body.
+pub fn parse(request: &Request) -> Result<FormData, String> {
+    let mime = request
+        .content_type()
+        .ok_or("Missing Content-Type".to_string())?;
+
+    if mime.essence() != URLENCODED {
+        return Err(format!("Expected {}, got {}", URLENCODED, mime.essence()));
+    }
+
+    if mime
+        .charset()
+        .is_some_and(|charset| !charset.eq_ignore_ascii_case("utf-8"))
+    {
+        return Err(format!("Unsupported charset: {}", mime.charset().unwrap()));
+    }
+
+    let body = std::str::from_utf8(request.body_bytes())
+        .map_err(|_| "Form body is not valid UTF-8".to_string())?;
+
+    Ok(FormData::new(url::parse_pairs(body)))
+}
+
+/// A form body mapped into a type, through the JSON object produced by
+/// [`FormData::to_json`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Form<T>(pub T);
+
+impl<T: Serde> Form<T> {
+    /// Reads the form, answering 415 if the request is not a URL-encoded form
+    /// and 400 if the fields do not map into `T`.
+    pub fn from_request(request: &Request) -> Result<Form<T>, Response> {
+        let is_form = request
+            .content_type()
+            .is_some_and(|mime| mime.essence() == URLENCODED);
+
+        if !is_form {
+            return Err(response::text(
+                HttpStatus::StatusUnsupportedMediaType,
+                format!("Expected {}", URLENCODED),
+            ));
+        }
+
+        let bad_request = |message: String| response::text(HttpStatus::StatusBadRequest, message);
+        let form = parse(request).map_err(bad_request)?;
+
+        T::from_json(&form.to_json()).map(Form).map_err(bad_request)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+#[cfg(test)]
+#[path = "./form_test.rs"]
+mod test;
@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use badserde::json::Serde;
+
+    use super::super::Form;
+    use crate::request;
+
+    #[derive(Debug, PartialEq)]
+    struct Login {
+        user: String,
+        remember: bool,
+    }
+
+    impl Serde for Login {
+        fn to_json(&self) -> String {
+            unimplemented!()
+        }
+
+        fn from_json(json: &str) -> Result<Self, String> {
+            let fields = HashMap::<String, String>::from_json(json)?;
+            let field = |name: &str| fields.get(name).ok_or(format!("Missing {}", name));
+            Ok(Login {
+                user: field("user")?.clone(),
+                remember: field("remember")?.parse().map_err(|_| "Invalid remember")?,
+            })
+        }
+    }
+
+    fn post(content_type: &str, body: &str) -> request::Request {
+        request::parse(&format!(
+            "POST /login HTTP/1.1\r\nContent-Type: {}\r\n\r\n{}",
+            content_type, body
+        ))
+    }
+
+    #[test]
+    fn test_form() {
+        let request = post(
+            "application/x-www-form-urlencoded",
+            "name=Jane+Doe&tag=a&tag=b%26c&empty&note=caf%C3%A9",
+        );
+        let form = request.form().unwrap();
+
+        assert_eq!(form.get("name"), Some("Jane Doe"));
+        assert_eq!(form.get_all("tag"), vec!["a", "b&c"]);
+        assert_eq!(form.get("empty"), Some(""));
+        assert_eq!(form.get("note"), Some("café"));
+        assert!(!form.contains("missing"));
+    }
+
+    #[test]
+    fn test_form_wrong_content_type() {
+        let request = post("application/json", "{}");
+        assert!(request.form().is_err());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let request = post(
+            "application/x-www-form-urlencoded; charset=UTF-8",
+            "a=1&b=%22x%22&a=2",
+        );
+        assert_eq!(
+            request.form().unwrap().to_json(),
+            r#"{"a": ["1", "2"], "b": "\"x\""}"#
+        );
+    }
+
+    #[test]
+    fn test_form_into_map() {
+        let request = post(
+            "application/x-www-form-urlencoded",
+            "name=Doe%2C+Jane&time=10%3A30&q=%22a%22%3A+%5B1%2C2%5D",
+        );
+
+        let Form(fields) = Form::<HashMap<String, String>>::from_request(&request)
+            .ok()
+            .unwrap();
+        assert_eq!(fields["name"], "Doe, Jane");
+        assert_eq!(fields["time"], "10:30");
+        assert_eq!(fields["q"], "\"a\": [1,2]");
+
+        let request = post("application/x-www-form-urlencoded", "a=1%2C2&a=3%3A4");
+        let Form(fields) = Form::<HashMap<String, Vec<String>>>::from_request(&request)
+            .ok()
+            .unwrap();
+        assert_eq!(fields["a"], vec!["1,2", "3:4"]);
+    }
+
+    #[test]
+    fn test_form_extractor() {
+        let request = post(
+            "application/x-www-form-urlencoded",
+            "user=Doe%2C+Jane%3A+admin&remember=true",
+        );
+        let Form(login) = Form::<Login>::from_request(&request).ok().unwrap();
+        assert_eq!(
+            login,
+            Login {
+                user: "Doe, Jane: admin".to_string(),
+                remember: true
+            }
+        );
+
+        let request = post("application/x-www-form-urlencoded", "user=admin");
+        let response = Form::<Login>::from_request(&request).unwrap_err();
+        assert_eq!(response.status().to_code(), 400);
+
+        let request = post("text/plain", "user=admin&remember=true");
+        let response = Form::<Login>::from_request(&request).unwrap_err();
+        assert_eq!(response.status().to_code(), 415);
+    }
+}
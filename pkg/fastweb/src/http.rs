@@ -1,9 +1,32 @@
+pub mod date;
+pub mod mime;
+
+pub use mime::Mime;
+
 use std::fmt::{Display, Formatter, Result};
 pub enum HttpStatus {
     StatusOK,
     StatusBadRequest,
     StatusNotFound,
     StatusCreated,
+    StatusMovedPermanently,
+    StatusPermanentRedirect,
+    StatusNotModified,
+    StatusPartialContent,
+    StatusRangeNotSatisfiable,
+    StatusInternalServerError,
+    StatusNotAcceptable,
+    StatusUnsupportedMediaType,
+    StatusContentTooLarge,
+    StatusPreconditionFailed,
+    StatusURITooLong,
+    StatusRequestHeaderFieldsTooLarge,
+    StatusContinue,
+    StatusExpectationFailed,
+    StatusHTTPVersionNotSupported,
+    StatusRequestTimeout,
+    StatusServiceUnavailable,
+    StatusNotImplemented,
 }
 
 impl Display for HttpStatus {
@@ -19,27 +42,83 @@ impl Clone for HttpStatus {
             HttpStatus::StatusNotFound => HttpStatus::StatusNotFound,
             HttpStatus::StatusBadRequest => HttpStatus::StatusBadRequest,
             HttpStatus::StatusCreated => HttpStatus::StatusCreated,
+            HttpStatus::StatusMovedPermanently => HttpStatus::StatusMovedPermanently,
+            HttpStatus::StatusPermanentRedirect => HttpStatus::StatusPermanentRedirect,
+            HttpStatus::StatusNotModified => HttpStatus::StatusNotModified,
+            HttpStatus::StatusPartialContent => HttpStatus::StatusPartialContent,
+            HttpStatus::StatusRangeNotSatisfiable => HttpStatus::StatusRangeNotSatisfiable,
+            HttpStatus::StatusInternalServerError => HttpStatus::StatusInternalServerError,
+            HttpStatus::StatusNotAcceptable => HttpStatus::StatusNotAcceptable,
+            HttpStatus::StatusUnsupportedMediaType => HttpStatus::StatusUnsupportedMediaType,
+            HttpStatus::StatusContentTooLarge => HttpStatus::StatusContentTooLarge,
+            HttpStatus::StatusPreconditionFailed => HttpStatus::StatusPreconditionFailed,
+            HttpStatus::StatusURITooLong => HttpStatus::StatusURITooLong,
+            HttpStatus::StatusRequestHeaderFieldsTooLarge => {
+                HttpStatus::StatusRequestHeaderFieldsTooLarge
+            }
+            HttpStatus::StatusContinue => HttpStatus::StatusContinue,
+            HttpStatus::StatusExpectationFailed => HttpStatus::StatusExpectationFailed,
+            HttpStatus::StatusHTTPVersionNotSupported => HttpStatus::StatusHTTPVersionNotSupported,
+            HttpStatus::StatusRequestTimeout => HttpStatus::StatusRequestTimeout,
+            HttpStatus::StatusServiceUnavailable => HttpStatus::StatusServiceUnavailable,
+            HttpStatus::StatusNotImplemented => HttpStatus::StatusNotImplemented,
         }
     }
 }
 
 impl HttpStatus {
     pub fn to_code(&self) -> u32 {
-        return match self {
+        match self {
             HttpStatus::StatusOK => 200,
             HttpStatus::StatusNotFound => 404,
             HttpStatus::StatusBadRequest => 400,
             HttpStatus::StatusCreated => 201,
-        };
+            HttpStatus::StatusMovedPermanently => 301,
+            HttpStatus::StatusPermanentRedirect => 308,
+            HttpStatus::StatusNotModified => 304,
+            HttpStatus::StatusPartialContent => 206,
+            HttpStatus::StatusRangeNotSatisfiable => 416,
+            HttpStatus::StatusInternalServerError => 500,
+            HttpStatus::StatusNotAcceptable => 406,
+            HttpStatus::StatusUnsupportedMediaType => 415,
+            HttpStatus::StatusContentTooLarge => 413,
+            HttpStatus::StatusPreconditionFailed => 412,
+            HttpStatus::StatusURITooLong => 414,
+            HttpStatus::StatusRequestHeaderFieldsTooLarge => 431,
+            HttpStatus::StatusContinue => 100,
+            HttpStatus::StatusExpectationFailed => 417,
+            HttpStatus::StatusHTTPVersionNotSupported => 505,
+            HttpStatus::StatusRequestTimeout => 408,
+            HttpStatus::StatusServiceUnavailable => 503,
+            HttpStatus::StatusNotImplemented => 501,
+        }
     }
 
     pub fn to_str(&self) -> &str {
-        return match self {
+        match self {
             HttpStatus::StatusOK => "OK",
             HttpStatus::StatusNotFound => "Not Found",
             HttpStatus::StatusBadRequest => "Bad Request",
             HttpStatus::StatusCreated => "Created",
-        };
+            HttpStatus::StatusMovedPermanently => "Moved Permanently",
+            HttpStatus::StatusPermanentRedirect => "Permanent Redirect",
+            HttpStatus::StatusNotModified => "Not Modified",
+            HttpStatus::StatusPartialContent => "Partial Content",
+            HttpStatus::StatusRangeNotSatisfiable => "Range Not Satisfiable",
+            HttpStatus::StatusInternalServerError => "Internal Server Error",
+            HttpStatus::StatusNotAcceptable => "Not Acceptable",
+            HttpStatus::StatusUnsupportedMediaType => "Unsupported Media Type",
+            HttpStatus::StatusContentTooLarge => "Content Too Large",
+            HttpStatus::StatusPreconditionFailed => "Precondition Failed",
+            HttpStatus::StatusURITooLong => "URI Too Long",
+            HttpStatus::StatusRequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
+            HttpStatus::StatusContinue => "Continue",
+            HttpStatus::StatusExpectationFailed => "Expectation Failed",
+            HttpStatus::StatusHTTPVersionNotSupported => "HTTP Version Not Supported",
+            HttpStatus::StatusRequestTimeout => "Request Timeout",
+            HttpStatus::StatusServiceUnavailable => "Service Unavailable",
+            HttpStatus::StatusNotImplemented => "Not Implemented",
+        }
     }
 }
 
@@ -56,7 +135,7 @@ pub enum HttpMethod {
 
 impl HttpMethod {
     fn to_str(&self) -> &str {
-        return match self {
+        match self {
             HttpMethod::GET => "GET",
             HttpMethod::POST => "POST",
             HttpMethod::PUT => "PUT",
@@ -64,15 +143,20 @@ impl HttpMethod {
             HttpMethod::HEAD => "HEAD",
             HttpMethod::PATCH => "PATCH",
             HttpMethod::ALL => "*",
-        };
+        }
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<HttpMethod> {
-        return match s {
+        match s {
             "GET" => Some(HttpMethod::GET),
             "POST" => Some(HttpMethod::POST),
+            "PUT" => Some(HttpMethod::PUT),
+            "DELETE" => Some(HttpMethod::DELETE),
+            "HEAD" => Some(HttpMethod::HEAD),
+            "PATCH" => Some(HttpMethod::PATCH),
             _ => None,
-        };
+        }
     }
 }
 
@@ -82,19 +166,83 @@ impl Display for HttpMethod {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum HttpVersion {
+    Http10,
+    #[default]
+    Http11,
+}
+
+impl HttpVersion {
+    pub fn to_str(&self) -> &str {
+        match self {
+            HttpVersion::Http10 => "HTTP/1.0",
+            HttpVersion::Http11 => "HTTP/1.1",
+        }
+    }
+
+    /// Parses the version of a request line, `None` for versions the server
+    /// does not speak.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(s: &str) -> Option<HttpVersion> {
+        match s {
+            "HTTP/1.0" => Some(HttpVersion::Http10),
+            "HTTP/1.1" => Some(HttpVersion::Http11),
+            _ => None,
+        }
+    }
+}
+
+impl Display for HttpVersion {
+    fn fmt(&self, f: &mut Formatter) -> Result {
+        write!(f, "{}", self.to_str())
+    }
+}
+
 pub enum HttpContentType {
     HTML,
     JSON,
     TEXT,
+    Other(String),
 }
 
 impl HttpContentType {
     pub fn to_str(&self) -> &str {
-        return match self {
-            HttpContentType::HTML => "text/html",
+        match self {
+            HttpContentType::HTML => "text/html; charset=utf-8",
             HttpContentType::JSON => "application/json",
-            HttpContentType::TEXT => "application/text",
-        };
+            HttpContentType::TEXT => "text/plain; charset=utf-8",
+            HttpContentType::Other(mime) => mime,
+        }
+    }
+
+    pub fn mime(&self) -> Mime {
+        Mime::parse(self.to_str()).unwrap_or(Mime::new("application", "octet-stream"))
+    }
+
+    pub fn from_extension(extension: &str) -> HttpContentType {
+        HttpContentType::from(Mime::from_extension(extension))
+    }
+}
+
+impl From<Mime> for HttpContentType {
+    fn from(mime: Mime) -> Self {
+        HttpContentType::from(mime.to_string().as_str())
+    }
+}
+
+impl From<&str> for HttpContentType {
+    fn from(mime: &str) -> Self {
+        let known = [
+            HttpContentType::HTML,
+            HttpContentType::JSON,
+            HttpContentType::TEXT,
+        ];
+
+        match known.into_iter().find(|known| known.to_str() == mime) {
+            Some(known) => known,
+            None => HttpContentType::Other(mime.to_string()),
+        }
     }
 }
 
@@ -111,27 +259,33 @@ pub enum HttpProtocol {
 #[derive(Debug, Clone, PartialEq, Default)]
 pub enum Encoding {
     GZIP,
+    DEFLATE,
     #[default]
-    None,
+    Identity,
 }
 
 impl Encoding {
     pub fn to_str(&self) -> &str {
-        return match self {
+        match self {
             Encoding::GZIP => "gzip",
-            Encoding::None => "none",
-        };
+            Encoding::DEFLATE => "deflate",
+            Encoding::Identity => "identity",
+        }
     }
 
+    /// Content codings the server can apply, in order of preference.
     pub fn get_supported() -> Vec<Encoding> {
-        return vec![Encoding::GZIP];
+        vec![Encoding::GZIP, Encoding::DEFLATE]
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Encoding> {
-        return match s {
-            "gzip" => Some(Encoding::GZIP),
+        match s.to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Encoding::GZIP),
+            "deflate" => Some(Encoding::DEFLATE),
+            "identity" => Some(Encoding::Identity),
             _ => None,
-        };
+        }
     }
 }
 
@@ -139,14 +293,62 @@ pub enum Headers {
     AcceptEncoding,
     ContentType,
     ContentLength,
+    Location,
+    ETag,
+    LastModified,
+    IfNoneMatch,
+    IfModifiedSince,
+    Range,
+    IfRange,
+    ContentRange,
+    AcceptRanges,
+    TransferEncoding,
+    Trailer,
+    Accept,
+    AcceptLanguage,
+    Vary,
+    ContentEncoding,
+    IfMatch,
+    IfUnmodifiedSince,
+    Date,
+    Server,
+    Cookie,
+    SetCookie,
+    Expect,
+    Connection,
+    RetryAfter,
 }
 
 impl Headers {
     pub fn to_str(&self) -> &str {
-        return match self {
+        match self {
             Headers::AcceptEncoding => "Accept-Encoding",
             Headers::ContentType => "Content-Type",
             Headers::ContentLength => "Content-Length",
-        };
+            Headers::Location => "Location",
+            Headers::ETag => "ETag",
+            Headers::LastModified => "Last-Modified",
+            Headers::IfNoneMatch => "If-None-Match",
+            Headers::IfModifiedSince => "If-Modified-Since",
+            Headers::Range => "Range",
+            Headers::IfRange => "If-Range",
+            Headers::ContentRange => "Content-Range",
+            Headers::AcceptRanges => "Accept-Ranges",
+            Headers::TransferEncoding => "Transfer-Encoding",
+            Headers::Trailer => "Trailer",
+            Headers::Accept => "Accept",
+            Headers::AcceptLanguage => "Accept-Language",
+            Headers::Vary => "Vary",
+            Headers::ContentEncoding => "Content-Encoding",
+            Headers::IfMatch => "If-Match",
+            Headers::IfUnmodifiedSince => "If-Unmodified-Since",
+            Headers::Date => "Date",
+            Headers::Server => "Server",
+            Headers::Cookie => "Cookie",
+            Headers::SetCookie => "Set-Cookie",
+            Headers::Expect => "Expect",
+            Headers::Connection => "Connection",
+            Headers::RetryAfter => "Retry-After",
+        }
     }
 }
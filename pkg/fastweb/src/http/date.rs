@@ -0,0 +1,209 @@
+use std::{
+    cell::RefCell,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+static DAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+static WEEKDAYS: [&str; 7] = [
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+    "Sunday",
+];
+static MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+// Converts days since the Unix epoch into a (year, month, day) civil date.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Converts a civil date into days since the Unix epoch.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Drops the sub-second part of a time, which HTTP dates cannot represent,
+/// so it compares equal to its formatted and parsed back value.
+pub fn truncate(time: SystemTime) -> SystemTime {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// Formats a time as an IMF-fixdate, e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+pub fn format(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs() as i64;
+
+    let days = secs.div_euclid(86400);
+    let secs_of_day = secs.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+
+    format!(
+        "{}, {:02} {} {:04} {:02}:{:02}:{:02} GMT",
+        DAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[month as usize - 1],
+        year,
+        secs_of_day / 3600,
+        secs_of_day % 3600 / 60,
+        secs_of_day % 60
+    )
+}
+
+/// Formats the current time, reusing the value formatted earlier in the same
+/// second on this thread.
+pub fn now() -> String {
+    thread_local! {
+        static CACHED: RefCell<(u64, String)> = const { RefCell::new((u64::MAX, String::new())) };
+    }
+
+    let now = SystemTime::now();
+    let secs = now
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+
+    CACHED.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        if cached.0 != secs {
+            *cached = (secs, format(now));
+        }
+        cached.1.clone()
+    })
+}
+
+/// Parses an HTTP date in any of the formats recipients must accept (RFC 9110
+/// section 5.6.7): IMF-fixdate, the obsolete RFC 850 format and asctime.
+/// Returns `None` if the value is malformed.
+pub fn parse(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    parse_imf_fixdate(value)
+        .or_else(|| parse_rfc850(value))
+        .or_else(|| parse_asctime(value))
+}
+
+// Sun, 06 Nov 1994 08:49:37 GMT
+fn parse_imf_fixdate(value: &str) -> Option<SystemTime> {
+    let (weekday, rest) = value.split_once(", ")?;
+    let mut parts = rest.split(' ');
+
+    let day = parts.next()?;
+    let month = parts.next()?;
+    let year = parts.next()?;
+    let time = parts.next()?;
+
+    if !DAYS.contains(&weekday) || day.len() != 2 || year.len() != 4 {
+        return None;
+    }
+
+    if parts.next()? != "GMT" || parts.next().is_some() {
+        return None;
+    }
+
+    to_system_time(year.parse().ok()?, month, day.parse().ok()?, time)
+}
+
+// Sunday, 06-Nov-94 08:49:37 GMT
+fn parse_rfc850(value: &str) -> Option<SystemTime> {
+    let (weekday, rest) = value.split_once(", ")?;
+    let mut parts = rest.split(' ');
+
+    let mut date = parts.next()?.split('-');
+    let time = parts.next()?;
+
+    if !WEEKDAYS.contains(&weekday) || parts.next()? != "GMT" || parts.next().is_some() {
+        return None;
+    }
+
+    let day = date.next()?;
+    let month = date.next()?;
+    let year = date.next()?;
+
+    if day.len() != 2 || year.len() != 2 || date.next().is_some() {
+        return None;
+    }
+
+    // A two digit year more than 50 years in the future is in the past century
+    // (RFC 9110 section 5.6.7)
+    let (current_year, _, _) = civil_from_days(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+            .as_secs() as i64
+            / 86400,
+    );
+    let mut year = current_year - current_year % 100 + year.parse::<i64>().ok()?;
+    if year > current_year + 50 {
+        year -= 100;
+    }
+
+    to_system_time(year, month, day.parse().ok()?, time)
+}
+
+// Sun Nov  6 08:49:37 1994
+fn parse_asctime(value: &str) -> Option<SystemTime> {
+    let mut parts = value.split(' ').filter(|part| !part.is_empty());
+
+    let weekday = parts.next()?;
+    let month = parts.next()?;
+    let day = parts.next()?;
+    let time = parts.next()?;
+    let year = parts.next()?;
+
+    if !DAYS.contains(&weekday) || day.len() > 2 || year.len() != 4 || parts.next().is_some() {
+        return None;
+    }
+
+    to_system_time(year.parse().ok()?, month, day.parse().ok()?, time)
+}
+
+fn to_system_time(year: i64, month: &str, day: u32, time: &str) -> Option<SystemTime> {
+    let month = MONTHS.iter().position(|m| *m == month)? as u32 + 1;
+
+    let mut clock = time.split(':');
+    let hour: u64 = clock.next()?.parse().ok()?;
+    let minute: u64 = clock.next()?.parse().ok()?;
+    let second: u64 = clock.next()?.parse().ok()?;
+
+    if clock.next().is_some() {
+        return None;
+    }
+
+    if !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 || year < 1970 {
+        return None;
+    }
+
+    let days = days_from_civil(year, month, day) as u64;
+    let secs = days * 86400 + hour * 3600 + minute * 60 + second;
+
+    Some(UNIX_EPOCH + Duration::from_secs(secs))
+}
+
+#[cfg(test)]
+#[path = "./date_test.rs"]
+mod test;
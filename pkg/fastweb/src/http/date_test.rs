@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{format, now, parse, truncate};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_format() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(format(time), "Sun, 06 Nov 1994 08:49:37 GMT");
+    }
+
+    #[test]
+    fn test_truncate() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777) + Duration::from_millis(250);
+        assert_eq!(truncate(time), UNIX_EPOCH + Duration::from_secs(784111777));
+        assert_eq!(parse(&format(time)), Some(truncate(time)));
+    }
+
+    #[test]
+    fn test_format_epoch() {
+        assert_eq!(format(UNIX_EPOCH), "Thu, 01 Jan 1970 00:00:00 GMT");
+    }
+
+    #[test]
+    fn test_parse() {
+        let time = parse("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(time, UNIX_EPOCH + Duration::from_secs(784111777));
+    }
+
+    #[test]
+    fn test_parse_obsolete_formats() {
+        let time = UNIX_EPOCH + Duration::from_secs(784111777);
+        assert_eq!(parse("Sunday, 06-Nov-94 08:49:37 GMT"), Some(time));
+        assert_eq!(parse("Sun Nov  6 08:49:37 1994"), Some(time));
+    }
+
+    #[test]
+    fn test_parse_rfc850_recent_year() {
+        let time = UNIX_EPOCH + Duration::from_secs(951782400);
+        assert_eq!(parse("Tuesday, 29-Feb-00 00:00:00 GMT"), Some(time));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(parse("Sun, 06 Nov 1994 08:49:37").is_none());
+        assert!(parse("yesterday").is_none());
+        assert!(parse("Sunday, 06-Nov-94 08:49:37").is_none());
+        assert!(parse("Sun Nov  6 08:49:37 1994 GMT").is_none());
+    }
+
+    #[test]
+    fn test_roundtrip_leap_year() {
+        let time = UNIX_EPOCH + Duration::from_secs(951782400);
+        assert_eq!(format(time), "Tue, 29 Feb 2000 00:00:00 GMT");
+        assert_eq!(parse(&format(time)).unwrap(), time);
+    }
+
+    #[test]
+    fn test_now() {
+        let first = now();
+        assert!(parse(&first).is_some());
+        assert!(first.ends_with(" GMT"));
+    }
+}
@@ -0,0 +1,205 @@
+use std::fmt::{Display, Formatter, Result};
+
+// Characters allowed in a token (RFC 9110 section 5.6.2).
+pub(crate) fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
+
+// Splits on `;` outside of quoted strings.
+pub(crate) fn split_params(value: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (index, c) in value.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ';' if !quoted => {
+                parts.push(&value[start..index]);
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+
+    parts.push(&value[start..]);
+    parts
+}
+
+pub(crate) fn unquote(value: &str) -> Option<String> {
+    let inner = match value.strip_prefix('"') {
+        Some(inner) => inner.strip_suffix('"')?,
+        None => return Some(value.to_string()),
+    };
+
+    let mut unquoted = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.push(chars.next()?),
+            c => unquoted.push(c),
+        }
+    }
+
+    Some(unquoted)
+}
+
+/// A media type such as `text/html; charset=utf-8`. Type, subtype and
+/// parameter names are case-insensitive and stored in lowercase.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mime {
+    type_: String,
+    subtype: String,
+    params: Vec<(String, String)>,
+}
+
+impl Mime {
+    pub fn new(type_: &str, subtype: &str) -> Mime {
+        Mime {
+            type_: type_.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            params: Vec::new(),
+        }
+    }
+
+    /// Parses a `Content-Type` style value, returning `None` if it is malformed.
+    pub fn parse(value: &str) -> Option<Mime> {
+        let mut parts = split_params(value).into_iter();
+        let (type_, subtype) = parts.next()?.trim().split_once('/')?;
+
+        if !is_token(type_) || !is_token(subtype) {
+            return None;
+        }
+
+        let mut mime = Mime::new(type_, subtype);
+
+        for param in parts
+            .map(|param| param.trim())
+            .filter(|param| !param.is_empty())
+        {
+            let (name, value) = param.split_once('=')?;
+            let (name, value) = (name.trim(), value.trim());
+
+            if !is_token(name) {
+                return None;
+            }
+
+            mime.params
+                .push((name.to_ascii_lowercase(), unquote(value)?));
+        }
+
+        Some(mime)
+    }
+
+    /// Returns the MIME type for a file extension, defaulting to
+    /// `application/octet-stream`.
+    pub fn from_extension(extension: &str) -> Mime {
+        let (essence, charset) = match extension.to_ascii_lowercase().as_str() {
+            "html" | "htm" => ("text/html", true),
+            "txt" => ("text/plain", true),
+            "css" => ("text/css", true),
+            "js" | "mjs" => ("text/javascript", true),
+            "csv" => ("text/csv", true),
+            "md" => ("text/markdown", true),
+            "json" => ("application/json", false),
+            "xml" => ("application/xml", false),
+            "svg" => ("image/svg+xml", false),
+            "png" => ("image/png", false),
+            "jpg" | "jpeg" => ("image/jpeg", false),
+            "gif" => ("image/gif", false),
+            "webp" => ("image/webp", false),
+            "avif" => ("image/avif", false),
+            "ico" => ("image/x-icon", false),
+            "woff" => ("font/woff", false),
+            "woff2" => ("font/woff2", false),
+            "ttf" => ("font/ttf", false),
+            "otf" => ("font/otf", false),
+            "wasm" => ("application/wasm", false),
+            "pdf" => ("application/pdf", false),
+            "zip" => ("application/zip", false),
+            "gz" => ("application/gzip", false),
+            "mp4" => ("video/mp4", false),
+            "webm" => ("video/webm", false),
+            "mp3" => ("audio/mpeg", false),
+            "ogg" => ("audio/ogg", false),
+            "wav" => ("audio/wav", false),
+            _ => ("application/octet-stream", false),
+        };
+
+        let mime = Mime::parse(essence).unwrap();
+
+        if charset {
+            return mime.with_param("charset", "utf-8");
+        }
+
+        mime
+    }
+
+    pub fn type_(&self) -> &str {
+        &self.type_
+    }
+
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// Returns `type/subtype` without parameters.
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_, self.subtype)
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn params(&self) -> &[(String, String)] {
+        &self.params
+    }
+
+    pub fn charset(&self) -> Option<&str> {
+        self.param("charset")
+    }
+
+    pub fn boundary(&self) -> Option<&str> {
+        self.param("boundary")
+    }
+
+    pub fn with_param(mut self, name: &str, value: &str) -> Mime {
+        self.params
+            .retain(|(key, _)| !key.eq_ignore_ascii_case(name));
+        self.params
+            .push((name.to_ascii_lowercase(), value.to_string()));
+        self
+    }
+}
+
+impl Display for Mime {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}/{}", self.type_, self.subtype)?;
+
+        for (name, value) in &self.params {
+            if is_token(value) {
+                write!(f, "; {}={}", name, value)?;
+            } else {
+                let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+                write!(f, "; {}=\"{}\"", name, escaped)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[path = "./mime_test.rs"]
+mod test;
@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod tests {
+    use super::super::Mime;
+
+    #[test]
+    fn test_parse() {
+        let mime = Mime::parse("Text/HTML; Charset=UTF-8").unwrap();
+        assert_eq!(mime.essence(), "text/html");
+        assert_eq!(mime.charset(), Some("UTF-8"));
+    }
+
+    #[test]
+    fn test_parse_quoted_params() {
+        let mime = Mime::parse(r#"multipart/form-data; boundary="a;b \"c\"""#).unwrap();
+        assert_eq!(mime.boundary(), Some(r#"a;b "c""#));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Mime::parse("text").is_none());
+        assert!(Mime::parse("text/").is_none());
+        assert!(Mime::parse("text/plain; charset").is_none());
+    }
+
+    #[test]
+    fn test_format() {
+        let mime = Mime::new("multipart", "form-data").with_param("boundary", "a b");
+        assert_eq!(mime.to_string(), "multipart/form-data; boundary=\"a b\"");
+        let mime = Mime::parse("text/plain;charset=utf-8").unwrap();
+        assert_eq!(mime.to_string(), "text/plain; charset=utf-8");
+    }
+
+    #[test]
+    fn test_from_extension() {
+        assert_eq!(Mime::from_extension("PNG").to_string(), "image/png");
+        assert_eq!(
+            Mime::from_extension("css").to_string(),
+            "text/css; charset=utf-8"
+        );
+        assert_eq!(
+            Mime::from_extension("unknown").to_string(),
+            "application/octet-stream"
+        );
+    }
+}
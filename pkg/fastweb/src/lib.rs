@@ -1,30 +1,72 @@
+pub mod body;
+pub mod compression;
+pub mod conditional;
+pub mod cookie;
+pub mod crypto;
+pub mod files;
+pub mod form;
 pub mod http;
+pub mod multipart;
+pub mod negotiation;
+pub mod range;
 pub mod request;
 pub mod response;
 pub mod router;
+pub mod server;
+pub mod session;
+pub mod stats;
+pub mod url;
 
-use router::{RouteTable, RouterBuilder};
+use compression::CompressionPolicy;
+use multipart::MultipartLimits;
+use router::{LoadShedding, PathNormalization, RouteTable, RouterBuilder};
+use session::Sessions;
+use stats::ServerStats;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 static CRLF: &str = "\r\n";
 static PATH_SEPARATOR: &str = "/";
-static DOUBLE_PATH_SEPARATOR: &str = "//";
 static QUERY_PARAM_KEY_VALUE_SEPARATOR: &str = "=";
 static QUERY_PARAM_SEPARATOR: &str = "&";
 static QUERY_PARAM_START: &str = "?";
 static EMPTY: &str = "";
 static LEFT_BRACKET: &str = "{";
 static RIGHT_BRACKET: &str = "}";
+static WILDCARD: &str = "*";
 
+#[derive(Clone)]
 pub struct Logging {
     level: String,
 }
 
+#[derive(Clone)]
 pub struct Configuration {
     port: u16,
     host: String,
     buffer_size: usize,
     logging: Logging,
     workers: usize,
+    path_normalization: PathNormalization,
+    compression: CompressionPolicy,
+    max_decompressed_size: u64,
+    server: Option<String>,
+    sessions: Option<Arc<Sessions>>,
+    multipart: MultipartLimits,
+    max_uri_length: usize,
+    max_header_bytes: usize,
+    max_header_count: usize,
+    max_body_size: u64,
+    header_read_timeout: Duration,
+    body_read_timeout: Duration,
+    write_timeout: Duration,
+    idle_timeout: Duration,
+    max_queued_connections: Option<usize>,
+    load_shedding: LoadShedding,
+    stats: Arc<ServerStats>,
+    drain_timeout: Duration,
+    handle_signals: bool,
 }
 
 pub fn new() -> RouterBuilder {
@@ -37,8 +79,28 @@ pub fn new() -> RouterBuilder {
                 level: "info".to_string(),
             },
             workers: 2,
+            path_normalization: PathNormalization::Strict,
+            compression: CompressionPolicy::default(),
+            max_decompressed_size: 10 * 1024 * 1024,
+            server: Some("fastweb".to_string()),
+            sessions: None,
+            multipart: MultipartLimits::default(),
+            max_uri_length: 8 * 1024,
+            max_header_bytes: 16 * 1024,
+            max_header_count: 100,
+            max_body_size: 10 * 1024 * 1024,
+            header_read_timeout: Duration::from_secs(10),
+            body_read_timeout: Duration::from_secs(30),
+            write_timeout: Duration::from_secs(30),
+            idle_timeout: Duration::from_secs(5),
+            max_queued_connections: None,
+            load_shedding: LoadShedding::Reject(Duration::from_secs(1)),
+            stats: Arc::new(ServerStats::default()),
+            drain_timeout: Duration::from_secs(30),
+            handle_signals: false,
         },
         routes: RouteTable(Vec::new()),
+        route_options: HashMap::new(),
     }
 }
 
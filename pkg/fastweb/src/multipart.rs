@@ -0,0 +1,528 @@
+use std::{
+    fmt::{Display, Formatter},
+    fs::{self, File, OpenOptions},
+    io::{self, Cursor, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use logger::debug;
+
+use crate::{
+    crypto,
+    http::{
+        mime::{split_params, unquote},
+        Headers, Mime,
+    },
+    url, CRLF,
+};
+
+pub static FORM_DATA: &str = "multipart/form-data";
+
+// Bytes requested from the reader at a time.
+static READ_CHUNK_SIZE: usize = 8 * 1024;
+// Limit on the headers of a single part.
+static MAX_PART_HEADER_SIZE: usize = 8 * 1024;
+
+/// Bounds what a multipart body may contain.
+#[derive(Debug, Clone)]
+pub struct MultipartLimits {
+    memory_limit: u64,
+    max_part_size: u64,
+    max_parts: usize,
+    temp_dir: PathBuf,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        MultipartLimits {
+            memory_limit: 64 * 1024,
+            max_part_size: 10 * 1024 * 1024,
+            max_parts: 100,
+            temp_dir: std::env::temp_dir(),
+        }
+    }
+}
+
+impl MultipartLimits {
+    pub fn new() -> MultipartLimits {
+        MultipartLimits::default()
+    }
+
+    /// Parts larger than this many bytes are spooled to a temporary file.
+    pub fn memory_limit(mut self, memory_limit: u64) -> MultipartLimits {
+        self.memory_limit = memory_limit;
+        self
+    }
+
+    /// Largest part accepted, in memory or on disk.
+    pub fn max_part_size(mut self, max_part_size: u64) -> MultipartLimits {
+        self.max_part_size = max_part_size;
+        self
+    }
+
+    pub fn max_parts(mut self, max_parts: usize) -> MultipartLimits {
+        self.max_parts = max_parts;
+        self
+    }
+
+    /// Directory spooled parts are written to, the system one by default.
+    pub fn temp_dir(mut self, temp_dir: &Path) -> MultipartLimits {
+        self.temp_dir = temp_dir.to_path_buf();
+        self
+    }
+}
+
+#[derive(Debug)]
+pub enum MultipartError {
+    /// The request is not `multipart/form-data` or has no boundary.
+    NotMultipart,
+    Malformed(String),
+    TooLarge,
+    Io(io::Error),
+}
+
+impl Display for MultipartError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MultipartError::NotMultipart => write!(f, "Expected {} with a boundary", FORM_DATA),
+            MultipartError::Malformed(reason) => write!(f, "Malformed multipart body: {}", reason),
+            MultipartError::TooLarge => write!(f, "Multipart body exceeds the size limits"),
+            MultipartError::Io(e) => write!(f, "Failed to read multipart body: {}", e),
+        }
+    }
+}
+
+impl From<io::Error> for MultipartError {
+    fn from(error: io::Error) -> Self {
+        MultipartError::Io(error)
+    }
+}
+
+fn malformed(reason: &str) -> MultipartError {
+    MultipartError::Malformed(reason.to_string())
+}
+
+/// A part spooled to a temporary file, removed when the part is dropped.
+#[derive(Debug)]
+pub struct SpooledFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+impl SpooledFile {
+    fn create(directory: &Path) -> io::Result<SpooledFile> {
+        let name = format!("fastweb-{}", crypto::to_hex(&crypto::random_bytes(16)));
+        let path = directory.join(name);
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        Ok(SpooledFile {
+            path,
+            file,
+            size: 0,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for SpooledFile {
+    fn drop(&mut self) {
+        // Already gone if the part was persisted
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[derive(Debug)]
+pub enum PartData {
+    Memory(Vec<u8>),
+    File(SpooledFile),
+}
+
+/// One part of a multipart body: a text field or an uploaded file.
+#[derive(Debug)]
+pub struct Part {
+    name: String,
+    filename: Option<String>,
+    headers: Vec<(String, String)>,
+    data: PartData,
+}
+
+impl Part {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The name of the uploaded file, `None` for plain fields. Browsers send
+    /// an empty name when no file was selected.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The declared type of the part, `text/plain` if none was sent
+    /// (RFC 7578 section 4.4).
+    pub fn content_type(&self) -> Mime {
+        self.header(Headers::ContentType.to_str())
+            .and_then(Mime::parse)
+            .unwrap_or_else(|| Mime::new("text", "plain"))
+    }
+
+    pub fn size(&self) -> u64 {
+        match &self.data {
+            PartData::Memory(bytes) => bytes.len() as u64,
+            PartData::File(file) => file.size,
+        }
+    }
+
+    pub fn data(&self) -> &PartData {
+        &self.data
+    }
+
+    /// Reads the whole content, from disk if the part was spooled.
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        match &self.data {
+            PartData::Memory(bytes) => Ok(bytes.clone()),
+            PartData::File(spooled) => fs::read(&spooled.path),
+        }
+    }
+
+    /// Returns the content as text, `None` if it is not valid UTF-8.
+    pub fn text(&self) -> Option<String> {
+        String::from_utf8(self.bytes().ok()?).ok()
+    }
+
+    pub fn reader(&self) -> io::Result<Box<dyn Read + Send>> {
+        match &self.data {
+            PartData::Memory(bytes) => Ok(Box::new(Cursor::new(bytes.clone()))),
+            PartData::File(spooled) => Ok(Box::new(File::open(&spooled.path)?)),
+        }
+    }
+
+    /// Saves the content to `path`, moving the temporary file when possible.
+    pub fn persist(&self, path: &Path) -> io::Result<()> {
+        match &self.data {
+            PartData::Memory(bytes) => fs::write(path, bytes),
+            PartData::File(spooled) => match fs::rename(&spooled.path, path) {
+                Ok(()) => Ok(()),
+                // Renaming fails across file systems
+                Err(_) => fs::copy(&spooled.path, path).map(|_| ()),
+            },
+        }
+    }
+}
+
+/// The parts of a `multipart/form-data` body (RFC 7578), in order.
+#[derive(Debug, Default)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+
+impl Multipart {
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    pub fn part(&self, name: &str) -> Option<&Part> {
+        self.parts.iter().find(|part| part.name == name)
+    }
+
+    /// Returns the value of the first text field with this name.
+    pub fn field(&self, name: &str) -> Option<String> {
+        self.parts
+            .iter()
+            .find(|part| part.name == name && part.filename.is_none())
+            .and_then(|part| part.text())
+    }
+
+    /// Returns the uploaded files, skipping file inputs left empty.
+    pub fn files(&self) -> Vec<&Part> {
+        self.parts
+            .iter()
+            .filter(|part| {
+                part.filename
+                    .as_deref()
+                    .is_some_and(|name| !name.is_empty())
+            })
+            .collect()
+    }
+}
+
+/// Returns the boundary of a `multipart/form-data` content type.
+pub fn boundary(content_type: &Mime) -> Option<&str> {
+    if content_type.essence() != FORM_DATA {
+        return None;
+    }
+
+    content_type
+        .boundary()
+        .filter(|boundary| (1..=70).contains(&boundary.len()))
+}
+
+// Buffers the reader so delimiters can be searched for across reads.
+struct Input<R: Read> {
+    reader: R,
+    buffer: Vec<u8>,
+    eof: bool,
+}
+
+impl<R: Read> Input<R> {
+    // Reads more data, returning false at the end of the input.
+    fn fill(&mut self) -> io::Result<bool> {
+        if self.eof {
+            return Ok(false);
+        }
+
+        let mut chunk = vec![0; READ_CHUNK_SIZE];
+        let read = loop {
+            match self.reader.read(&mut chunk) {
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+
+        self.eof = read == 0;
+        self.buffer.extend_from_slice(&chunk[..read]);
+        Ok(read > 0)
+    }
+
+    fn find(&self, needle: &[u8]) -> Option<usize> {
+        self.buffer
+            .windows(needle.len())
+            .position(|window| window == needle)
+    }
+
+    // Ensures at least `length` bytes are buffered.
+    fn require(&mut self, length: usize) -> Result<(), MultipartError> {
+        while self.buffer.len() < length {
+            if !self.fill()? {
+                return Err(malformed("unexpected end of body"));
+            }
+        }
+        Ok(())
+    }
+
+    fn consume(&mut self, length: usize) -> Vec<u8> {
+        self.buffer.drain(..length).collect()
+    }
+}
+
+// Collects the content of a part, moving it to disk once it outgrows memory.
+struct Sink<'a> {
+    limits: &'a MultipartLimits,
+    data: PartData,
+}
+
+impl Sink<'_> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), MultipartError> {
+        let size = match &self.data {
+            PartData::Memory(memory) => memory.len() as u64,
+            PartData::File(spooled) => spooled.size,
+        } + bytes.len() as u64;
+
+        if size > self.limits.max_part_size {
+            return Err(MultipartError::TooLarge);
+        }
+
+        if let PartData::Memory(memory) = &mut self.data {
+            if size > self.limits.memory_limit {
+                let mut spooled = SpooledFile::create(&self.limits.temp_dir)?;
+                debug!("Spooling multipart part to {:?}", spooled.path);
+                spooled.file.write_all(memory)?;
+                spooled.size = memory.len() as u64;
+                self.data = PartData::File(spooled);
+            }
+        }
+
+        match &mut self.data {
+            PartData::Memory(memory) => memory.extend_from_slice(bytes),
+            PartData::File(spooled) => {
+                spooled.file.write_all(bytes)?;
+                spooled.size += bytes.len() as u64;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn parse_headers(head: &[u8]) -> Result<Vec<(String, String)>, MultipartError> {
+    let head = std::str::from_utf8(head).map_err(|_| malformed("part headers are not UTF-8"))?;
+
+    head.split(CRLF)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let (name, value) = line
+                .split_once(':')
+                .ok_or_else(|| malformed("invalid part header"))?;
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+// Reads the field name and file name of a `Content-Disposition: form-data`
+// header, preferring an RFC 8187 `filename*` over `filename`.
+fn parse_disposition(value: &str) -> Result<(String, Option<String>), MultipartError> {
+    let mut params = split_params(value).into_iter();
+
+    if !params
+        .next()
+        .is_some_and(|kind| kind.trim().eq_ignore_ascii_case("form-data"))
+    {
+        return Err(malformed("part is not form-data"));
+    }
+
+    let (mut name, mut filename, mut extended) = (None, None, None);
+
+    for param in params {
+        let (key, value) = match param.split_once('=') {
+            Some((key, value)) => (key.trim().to_ascii_lowercase(), value.trim()),
+            None => continue,
+        };
+
+        match key.as_str() {
+            "name" => name = unquote(value),
+            "filename" => filename = unquote(value),
+            "filename*" => {
+                extended = value
+                    .split_once("''")
+                    .filter(|(charset, _)| charset.eq_ignore_ascii_case("utf-8"))
+                    .and_then(|(_, encoded)| url::decode(encoded))
+            }
+            _ => (),
+        }
+    }
+
+    let name = name.ok_or_else(|| malformed("part has no name"))?;
+    Ok((name, extended.or(filename)))
+}
+
+/// Parses a `multipart/form-data` body as it is read, so only a bounded
+/// window of it is held in memory and large parts go straight to disk.
+pub fn parse<R: Read>(
+    reader: R,
+    boundary: &str,
+    limits: &MultipartLimits,
+) -> Result<Multipart, MultipartError> {
+    let mut input = Input {
+        reader,
+        buffer: Vec::new(),
+        eof: false,
+    };
+    let mut multipart = Multipart::default();
+
+    // The first delimiter may follow a preamble, later ones follow a line break
+    let first = format!("--{}", boundary).into_bytes();
+    let delimiter = format!("{}--{}", CRLF, boundary).into_bytes();
+
+    loop {
+        if let Some(index) = input.find(&first) {
+            input.consume(index + first.len());
+            break;
+        }
+        if input.buffer.len() > MAX_PART_HEADER_SIZE || !input.fill()? {
+            return Err(malformed("missing opening boundary"));
+        }
+    }
+
+    loop {
+        // After a delimiter comes either `--`, closing the body, or a line break
+        input.require(2)?;
+        if input.buffer.starts_with(b"--") {
+            break;
+        }
+
+        loop {
+            if let Some(index) = input.find(CRLF.as_bytes()) {
+                let padding = input.consume(index + CRLF.len());
+                if padding[..index].iter().any(|b| !matches!(b, b' ' | b'\t')) {
+                    return Err(malformed("invalid boundary line"));
+                }
+                break;
+            }
+            if input.buffer.len() > MAX_PART_HEADER_SIZE || !input.fill()? {
+                return Err(malformed("invalid boundary line"));
+            }
+        }
+
+        if multipart.parts.len() == limits.max_parts {
+            return Err(MultipartError::TooLarge);
+        }
+
+        // A part without headers starts with the blank line right away
+        let separator = format!("{}{}", CRLF, CRLF);
+        input.require(CRLF.len())?;
+        let headers = if input.buffer.starts_with(CRLF.as_bytes()) {
+            input.consume(CRLF.len());
+            Vec::new()
+        } else {
+            loop {
+                if let Some(index) = input.find(separator.as_bytes()) {
+                    let head = input.consume(index + separator.len());
+                    break parse_headers(&head[..index])?;
+                }
+                if input.buffer.len() > MAX_PART_HEADER_SIZE {
+                    return Err(MultipartError::TooLarge);
+                }
+                if !input.fill()? {
+                    return Err(malformed("unexpected end of part headers"));
+                }
+            }
+        };
+
+        let disposition = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("Content-Disposition"))
+            .map(|(_, value)| value.as_str())
+            .ok_or_else(|| malformed("part has no Content-Disposition"))?;
+        let (name, filename) = parse_disposition(disposition)?;
+
+        let mut sink = Sink {
+            limits,
+            data: PartData::Memory(Vec::new()),
+        };
+
+        loop {
+            if let Some(index) = input.find(&delimiter) {
+                sink.write(&input.consume(index))?;
+                input.consume(delimiter.len());
+                break;
+            }
+
+            // Keep enough to recognise a delimiter split across reads
+            let safe = input.buffer.len().saturating_sub(delimiter.len() - 1);
+            if safe > 0 {
+                sink.write(&input.consume(safe))?;
+            }
+
+            if !input.fill()? {
+                return Err(malformed("missing closing boundary"));
+            }
+        }
+
+        multipart.parts.push(Part {
+            name,
+            filename,
+            headers,
+            data: sink.data,
+        });
+    }
+
+    Ok(multipart)
+}
+
+#[cfg(test)]
+#[path = "./multipart_test.rs"]
+mod test;
@@ -0,0 +1,133 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use super::super::{parse, MultipartError, MultipartLimits, PartData};
+    use crate::request;
+
+    static BOUNDARY: &str = "----fastweb42";
+
+    fn body(binary: &[u8]) -> Vec<u8> {
+        let mut body = format!(
+            "preamble\r\n--{b}\r\n\
+             Content-Disposition: form-data; name=\"title\"\r\n\r\n\
+             Holiday\r\n--{b}\r\n\
+             Content-Disposition: form-data; name=\"photo\"; filename=\"a.png\"\r\n\
+             Content-Type: image/png\r\n\r\n",
+            b = BOUNDARY
+        )
+        .into_bytes();
+        body.extend_from_slice(binary);
+        body.extend_from_slice(format!("\r\n--{}--\r\nepilogue", BOUNDARY).as_bytes());
+        body
+    }
+
+    // Hands out one byte per read, so delimiters always span reads.
+    struct Trickle(io::Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let binary = [0x89, b'P', b'N', b'G', b'\r', b'\n', b'-', b'-', 0x00, 0xff];
+        let body = body(&binary);
+        let multipart = parse(
+            Trickle(io::Cursor::new(body)),
+            BOUNDARY,
+            &MultipartLimits::new(),
+        )
+        .unwrap();
+
+        assert_eq!(multipart.parts().len(), 2);
+        assert_eq!(multipart.field("title"), Some("Holiday".to_string()));
+
+        let photo = multipart.part("photo").unwrap();
+        assert_eq!(photo.filename(), Some("a.png"));
+        assert_eq!(photo.content_type().essence(), "image/png");
+        assert_eq!(photo.bytes().unwrap(), binary);
+        assert_eq!(multipart.files().len(), 1);
+    }
+
+    #[test]
+    fn test_spool_to_file() {
+        let binary = vec![7u8; 5000];
+        let limits = MultipartLimits::new().memory_limit(1024);
+        let multipart = parse(body(&binary).as_slice(), BOUNDARY, &limits).unwrap();
+
+        let photo = multipart.part("photo").unwrap();
+        let path = match photo.data() {
+            PartData::File(spooled) => spooled.path().to_path_buf(),
+            PartData::Memory(_) => panic!("part should be spooled"),
+        };
+        assert_eq!(photo.size(), 5000);
+        assert_eq!(photo.bytes().unwrap(), binary);
+        assert!(matches!(
+            multipart.part("title").unwrap().data(),
+            PartData::Memory(_)
+        ));
+
+        drop(multipart);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_too_large() {
+        let limits = MultipartLimits::new().max_part_size(100);
+        let result = parse(body(&[0; 200]).as_slice(), BOUNDARY, &limits);
+        assert!(matches!(result, Err(MultipartError::TooLarge)));
+
+        let limits = MultipartLimits::new().max_parts(1);
+        let result = parse(body(&[0; 10]).as_slice(), BOUNDARY, &limits);
+        assert!(matches!(result, Err(MultipartError::TooLarge)));
+    }
+
+    #[test]
+    fn test_malformed() {
+        let mut truncated = body(b"data");
+        truncated.truncate(truncated.len() - 20);
+        let result = parse(truncated.as_slice(), BOUNDARY, &MultipartLimits::new());
+        assert!(matches!(result, Err(MultipartError::Malformed(_))));
+
+        let result = parse(&b"no boundary here"[..], BOUNDARY, &MultipartLimits::new());
+        assert!(matches!(result, Err(MultipartError::Malformed(_))));
+    }
+
+    #[test]
+    fn test_extended_filename() {
+        let body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"f\"; filename=\"x\"; \
+             filename*=UTF-8''r%C3%A9sum%C3%A9.pdf\r\n\r\n%PDF\r\n--{b}--",
+            b = BOUNDARY
+        );
+        let multipart = parse(body.as_bytes(), BOUNDARY, &MultipartLimits::new()).unwrap();
+        assert_eq!(multipart.part("f").unwrap().filename(), Some("résumé.pdf"));
+    }
+
+    #[test]
+    fn test_request_multipart() {
+        let mut raw = format!(
+            "POST /upload HTTP/1.1\r\nContent-Type: multipart/form-data; boundary=\"{}\"\r\n\r\n",
+            BOUNDARY
+        )
+        .into_bytes();
+        raw.extend(body(b"\x00\x01"));
+
+        let request = request::parse_bytes(&raw);
+        let multipart = request.multipart().unwrap();
+        assert_eq!(
+            multipart.part("photo").unwrap().bytes().unwrap(),
+            b"\x00\x01"
+        );
+
+        let request = request::parse("POST / HTTP/1.1\r\nContent-Type: text/plain\r\n\r\nhi");
+        assert!(matches!(
+            request.multipart(),
+            Err(MultipartError::NotMultipart)
+        ));
+    }
+}
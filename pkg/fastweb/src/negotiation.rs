@@ -0,0 +1,209 @@
+use crate::{
+    http::{Encoding, Headers, HttpContentType, HttpStatus, Mime},
+    request::Request,
+    response::{self, Response},
+};
+
+/// One element of a weighted header such as `Accept`, `Accept-Encoding` or
+/// `Accept-Language`, e.g. `text/html;level=1;q=0.8`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityItem {
+    pub value: String,
+    pub params: Vec<(String, String)>,
+    pub quality: f32,
+}
+
+// Parses a q-value (RFC 9110 section 12.4.2): 0 to 1 with at most 3 decimals.
+fn parse_quality(value: &str) -> Option<f32> {
+    let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+
+    if !matches!(whole, "0" | "1")
+        || fraction.len() > 3
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let quality: f32 = value.parse().ok()?;
+    (quality <= 1.0).then_some(quality)
+}
+
+/// Parses a comma separated list of weighted values. Elements with an invalid
+/// q-value are dropped; elements without one have a quality of 1.
+pub fn parse(header: &str) -> Vec<QualityItem> {
+    let mut items = Vec::new();
+
+    'elements: for element in header.split(',').map(|element| element.trim()) {
+        let mut parts = element.split(';').map(|part| part.trim());
+        let value = match parts.next() {
+            Some(value) if !value.is_empty() => value.to_ascii_lowercase(),
+            _ => continue,
+        };
+
+        let mut item = QualityItem {
+            value,
+            params: Vec::new(),
+            quality: 1.0,
+        };
+
+        for part in parts.filter(|part| !part.is_empty()) {
+            let (name, value) = part.split_once('=').unwrap_or((part, ""));
+            let (name, value) = (name.trim().to_ascii_lowercase(), value.trim());
+
+            if name == "q" {
+                match parse_quality(value) {
+                    Some(quality) => item.quality = quality,
+                    None => continue 'elements,
+                }
+                // Anything after q is an accept extension, not a media type parameter
+                break;
+            }
+
+            item.params
+                .push((name, value.trim_matches('"').to_string()));
+        }
+
+        items.push(item);
+    }
+
+    items
+}
+
+// How specifically a media range matches a type, higher is more specific.
+fn media_range_precedence(range: &QualityItem, offer: &Mime) -> Option<usize> {
+    let (type_, subtype) = range.value.split_once('/')?;
+
+    let precedence = match (type_, subtype) {
+        ("*", "*") => 1,
+        (type_, "*") if type_ == offer.type_() => 2,
+        (type_, subtype) if type_ == offer.type_() && subtype == offer.subtype() => 3,
+        _ => return None,
+    };
+
+    let params_match = range.params.iter().all(|(name, value)| {
+        offer
+            .param(name)
+            .is_some_and(|v| v.eq_ignore_ascii_case(value))
+    });
+
+    if !params_match {
+        return None;
+    }
+
+    Some(precedence * 100 + range.params.len())
+}
+
+/// Picks the best of the offered media types for the request's `Accept`
+/// header. Offers are in order of server preference, which breaks ties.
+/// Returns `None` if nothing offered is acceptable.
+pub fn media_type(request: &Request, offers: &[Mime]) -> Option<Mime> {
+    let ranges = match request.header(Headers::Accept.to_str()) {
+        Some(accept) => parse(accept),
+        None => return offers.first().cloned(),
+    };
+
+    best(offers, |offer| {
+        ranges
+            .iter()
+            .filter_map(|range| Some((media_range_precedence(range, offer)?, range.quality)))
+            .max_by_key(|(precedence, _)| *precedence)
+            .map(|(_, quality)| quality)
+    })
+}
+
+/// Picks the best of the offered language tags for the request's
+/// `Accept-Language` header, using basic filtering (RFC 4647 section 3.3.1).
+pub fn language<'a>(request: &Request, offers: &[&'a str]) -> Option<&'a str> {
+    let ranges = match request.header(Headers::AcceptLanguage.to_str()) {
+        Some(accept) => parse(accept),
+        None => return offers.first().copied(),
+    };
+
+    best(offers, |offer| {
+        let offer = offer.to_ascii_lowercase();
+        ranges
+            .iter()
+            .filter(|range| {
+                range.value == "*"
+                    || range.value == offer
+                    || offer.starts_with(&format!("{}-", range.value))
+            })
+            .max_by_key(|range| {
+                if range.value == "*" {
+                    0
+                } else {
+                    range.value.len()
+                }
+            })
+            .map(|range| range.quality)
+    })
+}
+
+/// Picks the content coding for the response from the request's
+/// `Accept-Encoding` header (RFC 9110 section 12.5.3). Returns `None` when the
+/// content should be sent unencoded.
+pub fn encoding(request: &Request) -> Option<Encoding> {
+    let items = match request.header(Headers::AcceptEncoding.to_str()) {
+        Some(accept) => parse(accept),
+        None => return None,
+    };
+
+    let wildcard = items.iter().find(|item| item.value == "*");
+
+    let quality = |encoding: &Encoding| {
+        let explicit = items
+            .iter()
+            .find(|item| Encoding::from_str(&item.value).as_ref() == Some(encoding));
+
+        match (explicit, wildcard, encoding) {
+            (Some(item), _, _) => Some(item.quality),
+            (None, Some(wildcard), _) => Some(wildcard.quality),
+            // Identity is always acceptable unless excluded
+            (None, None, Encoding::Identity) => Some(1.0),
+            (None, None, _) => None,
+        }
+    };
+
+    let mut offers = Encoding::get_supported();
+    offers.push(Encoding::Identity);
+
+    match best(&offers, quality) {
+        Some(Encoding::Identity) | None => None,
+        encoding => encoding,
+    }
+}
+
+// Returns the offer with the highest non-zero quality, the first one on ties.
+fn best<T: Clone>(offers: &[T], quality: impl Fn(&T) -> Option<f32>) -> Option<T> {
+    let mut best: Option<(&T, f32)> = None;
+
+    for offer in offers {
+        match quality(offer) {
+            Some(q) if q > 0.0 && best.is_none_or(|(_, best_q)| q > best_q) => {
+                best = Some((offer, q));
+            }
+            _ => (),
+        }
+    }
+
+    best.map(|(offer, _)| offer.clone())
+}
+
+/// A 406 response listing the representations the server can produce.
+pub fn not_acceptable(offers: &[Mime]) -> Response {
+    let available = offers
+        .iter()
+        .map(|offer| offer.essence())
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    response::new(
+        HttpStatus::StatusNotAcceptable,
+        available,
+        HttpContentType::TEXT,
+    )
+}
+
+#[cfg(test)]
+#[path = "./negotiation_test.rs"]
+mod test;
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{encoding, language, media_type, parse};
+    use crate::http::{Encoding, Mime};
+    use crate::request;
+
+    fn offers() -> Vec<Mime> {
+        vec![
+            Mime::parse("application/json").unwrap(),
+            Mime::parse("text/html").unwrap(),
+            Mime::parse("text/plain").unwrap(),
+        ]
+    }
+
+    fn with_header(name: &str, value: &str) -> request::Request {
+        request::parse(&format!("GET / HTTP/1.1\r\n{}: {}\r\n\r\n", name, value))
+    }
+
+    #[test]
+    fn test_parse_qualities() {
+        let items = parse("gzip;q=0.5, br, identity;q=0, deflate;q=2");
+        let values: Vec<(&str, f32)> = items
+            .iter()
+            .map(|item| (item.value.as_str(), item.quality))
+            .collect();
+        assert_eq!(values, vec![("gzip", 0.5), ("br", 1.0), ("identity", 0.0)]);
+    }
+
+    #[test]
+    fn test_parse_media_params() {
+        let items = parse("text/html;level=1;q=0.7;ext=1");
+        assert_eq!(
+            items[0].params,
+            vec![("level".to_string(), "1".to_string())]
+        );
+        assert_eq!(items[0].quality, 0.7);
+    }
+
+    #[test]
+    fn test_media_type_browser() {
+        let request = with_header(
+            "Accept",
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+        );
+        assert_eq!(
+            media_type(&request, &offers()).unwrap().essence(),
+            "text/html"
+        );
+    }
+
+    #[test]
+    fn test_media_type_specificity() {
+        let request = with_header("Accept", "text/*;q=0.3, text/plain, */*;q=0.1");
+        assert_eq!(
+            media_type(&request, &offers()).unwrap().essence(),
+            "text/plain"
+        );
+    }
+
+    #[test]
+    fn test_media_type_default_and_exclusion() {
+        let request = request::parse("GET / HTTP/1.1\r\n\r\n");
+        assert_eq!(
+            media_type(&request, &offers()).unwrap().essence(),
+            "application/json"
+        );
+
+        let request = with_header("Accept", "application/json;q=0, image/png");
+        assert!(media_type(&request, &offers()).is_none());
+    }
+
+    #[test]
+    fn test_language() {
+        let request = with_header("Accept-Language", "fr-CH, fr;q=0.9, en;q=0.8, *;q=0.5");
+        assert_eq!(language(&request, &["en-US", "fr"]), Some("fr"));
+        assert_eq!(language(&request, &["de", "en-GB"]), Some("en-GB"));
+    }
+
+    #[test]
+    fn test_encoding() {
+        let negotiate = |value: &str| encoding(&with_header("Accept-Encoding", value));
+        assert_eq!(negotiate("gzip, deflate, br"), Some(Encoding::GZIP));
+        assert_eq!(negotiate("deflate, gzip;q=0.5"), Some(Encoding::DEFLATE));
+        assert_eq!(negotiate("br"), None);
+        assert_eq!(negotiate(""), None);
+    }
+
+    #[test]
+    fn test_encoding_exclusions() {
+        let negotiate = |value: &str| encoding(&with_header("Accept-Encoding", value));
+        assert_eq!(negotiate("gzip;q=0"), None);
+        assert_eq!(negotiate("gzip;q=0, *"), Some(Encoding::DEFLATE));
+        assert_eq!(negotiate("*;q=0, identity"), None);
+        assert_eq!(negotiate("gzip;q=0.5, identity"), None);
+        assert_eq!(negotiate("identity;q=0, *"), Some(Encoding::GZIP));
+    }
+}
@@ -0,0 +1,244 @@
+use std::{
+    collections::VecDeque,
+    io,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use logger::{debug, error};
+
+use crate::{
+    body::{Body, READ_CHUNK_SIZE},
+    http::{Headers, HttpContentType, HttpStatus},
+    response::{self, Response},
+    CRLF,
+};
+
+static BYTES_UNIT: &str = "bytes=";
+
+// Requests with more ranges than this are answered with the full content,
+// which RFC 9110 allows and which keeps overlapping range abuse cheap.
+static MAX_RANGES: usize = 16;
+
+#[derive(Debug, PartialEq)]
+pub enum Ranges {
+    /// Inclusive byte ranges in ascending order, overlapping and adjacent
+    /// ones merged.
+    Satisfiable(Vec<(u64, u64)>),
+    Unsatisfiable,
+}
+
+/// Parses a `Range` header against content of the given length. Returns `None`
+/// when the header is malformed or uses an unknown unit, in which case it
+/// must be ignored, and when the ranges add up to more than the content,
+/// which is then cheaper to send whole.
+pub fn parse(value: &str, length: u64) -> Option<Ranges> {
+    let specs = value.trim().strip_prefix(BYTES_UNIT)?;
+    let mut ranges = Vec::new();
+
+    for spec in specs.split(',').map(|spec| spec.trim()) {
+        let (start, end) = spec.split_once('-')?;
+
+        let range = if start.is_empty() {
+            let suffix: u64 = end.parse().ok()?;
+            if suffix == 0 || length == 0 {
+                None
+            } else {
+                Some((length.saturating_sub(suffix), length - 1))
+            }
+        } else {
+            let start: u64 = start.parse().ok()?;
+            let end: u64 = match end {
+                "" => u64::MAX,
+                end => end.parse().ok()?,
+            };
+
+            if end < start {
+                return None;
+            }
+
+            if start < length {
+                Some((start, end.min(length - 1)))
+            } else {
+                None
+            }
+        };
+
+        if let Some(range) = range {
+            ranges.push(range);
+        }
+    }
+
+    if ranges.len() > MAX_RANGES {
+        return None;
+    }
+
+    if ranges.is_empty() {
+        return Some(Ranges::Unsatisfiable);
+    }
+
+    let requested: u64 = ranges.iter().map(|(start, end)| end - start + 1).sum();
+    if requested > length {
+        return None;
+    }
+
+    Some(Ranges::Satisfiable(merge(ranges)))
+}
+
+// Sorts the ranges and merges the overlapping and adjacent ones, as RFC 9110
+// allows, so no byte is sent twice.
+fn merge(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end + 1 => *last_end = end.max(*last_end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    merged
+}
+
+// An If-Range validator matches when it is the response's strong ETag or its
+// exact Last-Modified date.
+fn if_range_matches(response: &Response, if_range: &str) -> bool {
+    let if_range = if_range.trim();
+
+    if if_range.starts_with('"') {
+        return response.header(Headers::ETag.to_str()) == Some(if_range);
+    }
+
+    response.header(Headers::LastModified.to_str()) == Some(if_range)
+}
+
+fn boundary() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos())
+        .unwrap_or_default();
+    format!("fastweb-{:x}", nanos)
+}
+
+fn fail(response: &mut Response, e: io::Error) {
+    error!("Error reading range: {}", e);
+    *response = response::new(
+        HttpStatus::StatusInternalServerError,
+        String::new(),
+        HttpContentType::TEXT,
+    );
+}
+
+/// Narrows a full `200 OK` response down to the byte ranges requested by the
+/// client, replying 206 for satisfiable ranges and 416 otherwise.
+pub fn apply(response: &mut Response, range: &str, if_range: Option<&str>) {
+    if response.status().to_code() != HttpStatus::StatusOK.to_code() {
+        return;
+    }
+
+    if let Some(if_range) = if_range {
+        if !if_range_matches(response, if_range) {
+            debug!("If-Range validator did not match, serving full content");
+            return;
+        }
+    }
+
+    // Streamed content has no known length to take ranges from
+    let length = match response.content.size() {
+        Some(length) => length,
+        None => return,
+    };
+    let content = std::mem::replace(&mut response.content, Body::Bytes(Vec::new()));
+
+    let ranges = match parse(range, length) {
+        Some(Ranges::Satisfiable(ranges)) => ranges,
+        Some(Ranges::Unsatisfiable) => {
+            debug!("Unsatisfiable range {} for length {}", range, length);
+            response.status = HttpStatus::StatusRangeNotSatisfiable;
+            response.set_header(
+                Headers::ContentRange.to_str(),
+                &format!("bytes */{}", length),
+            );
+            return;
+        }
+        None => {
+            response.content = content;
+            return;
+        }
+    };
+
+    response.status = HttpStatus::StatusPartialContent;
+
+    if let [(start, end)] = ranges[..] {
+        response.content = match content.slice(start, end) {
+            Ok(content) => content,
+            Err(e) => return fail(response, e),
+        };
+        response.set_header(
+            Headers::ContentRange.to_str(),
+            &format!("bytes {}-{}/{}", start, end, length),
+        );
+        return;
+    }
+
+    let boundary = boundary();
+    let mut parts = VecDeque::new();
+
+    for (start, end) in ranges {
+        parts.push_back(Part::Text(format!(
+            "--{}{}{}: {}{}{}: bytes {}-{}/{}{}{}",
+            boundary,
+            CRLF,
+            Headers::ContentType.to_str(),
+            response.content_type.to_str(),
+            CRLF,
+            Headers::ContentRange.to_str(),
+            start,
+            end,
+            length,
+            CRLF,
+            CRLF
+        )));
+        parts.push_back(Part::Range(start, end));
+        parts.push_back(Part::Text(CRLF.to_string()));
+    }
+
+    parts.push_back(Part::Text(format!("--{}--{}", boundary, CRLF)));
+
+    response.content = Body::Stream(Box::new(Multipart { content, parts }));
+    response.content_type =
+        HttpContentType::Other(format!("multipart/byteranges; boundary={}", boundary));
+}
+
+enum Part {
+    Text(String),
+    Range(u64, u64),
+}
+
+// The parts of a multipart/byteranges body, reading each range from the
+// content a chunk at a time as it is sent.
+struct Multipart {
+    content: Body,
+    parts: VecDeque<Part>,
+}
+
+impl Iterator for Multipart {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.parts.pop_front()? {
+            Part::Text(text) => Some(Ok(text.into_bytes())),
+            Part::Range(start, end) => {
+                let chunk_end = end.min(start + READ_CHUNK_SIZE as u64 - 1);
+                if chunk_end < end {
+                    self.parts.push_front(Part::Range(chunk_end + 1, end));
+                }
+                Some(self.content.read_range(start, chunk_end))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./range_test.rs"]
+mod test;
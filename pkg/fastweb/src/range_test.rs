@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{apply, parse, Ranges};
+    use crate::http::HttpStatus;
+    use crate::response;
+
+    #[test]
+    fn test_parse_ranges() {
+        assert_eq!(
+            parse("bytes=0-4", 10),
+            Some(Ranges::Satisfiable(vec![(0, 4)]))
+        );
+        assert_eq!(
+            parse("bytes=5-", 10),
+            Some(Ranges::Satisfiable(vec![(5, 9)]))
+        );
+        assert_eq!(
+            parse("bytes=-3", 10),
+            Some(Ranges::Satisfiable(vec![(7, 9)]))
+        );
+        assert_eq!(
+            parse("bytes=8-20", 10),
+            Some(Ranges::Satisfiable(vec![(8, 9)]))
+        );
+    }
+
+    #[test]
+    fn test_parse_multiple_ranges() {
+        assert_eq!(
+            parse("bytes=0-1, 4-5", 10),
+            Some(Ranges::Satisfiable(vec![(0, 1), (4, 5)]))
+        );
+    }
+
+    #[test]
+    fn test_parse_merges_ranges() {
+        assert_eq!(
+            parse("bytes=4-5, 0-1, 1-2, 6-6", 10),
+            Some(Ranges::Satisfiable(vec![(0, 2), (4, 6)]))
+        );
+    }
+
+    #[test]
+    fn test_parse_ranges_larger_than_content() {
+        assert_eq!(parse("bytes=0-,0-", 10), None);
+        assert_eq!(parse("bytes=0-5,-5", 10), None);
+    }
+
+    #[test]
+    fn test_parse_unsatisfiable() {
+        assert_eq!(parse("bytes=10-", 10), Some(Ranges::Unsatisfiable));
+        assert_eq!(parse("bytes=-0", 10), Some(Ranges::Unsatisfiable));
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert_eq!(parse("items=0-1", 10), None);
+        assert_eq!(parse("bytes=5-1", 10), None);
+        assert_eq!(parse("bytes=a-b", 10), None);
+    }
+
+    #[test]
+    fn test_apply_single_range() {
+        let mut response = response::text(HttpStatus::StatusOK, "0123456789".to_string());
+        apply(&mut response, "bytes=2-4", None);
+        assert_eq!(response.status().to_code(), 206);
+        assert_eq!(response.content.as_bytes(), Some(&b"234"[..]));
+        assert_eq!(response.header("Content-Range"), Some("bytes 2-4/10"));
+    }
+
+    #[test]
+    fn test_apply_multiple_ranges() {
+        let mut response = response::text(HttpStatus::StatusOK, "0123456789".to_string());
+        apply(&mut response, "bytes=0-0,9-9", None);
+        assert_eq!(response.status().to_code(), 206);
+        assert_eq!(response.content.size(), None);
+        assert!(response
+            .content_type
+            .to_str()
+            .starts_with("multipart/byteranges"));
+        let body = String::from_utf8(response.content.into_bytes().unwrap()).unwrap();
+        assert!(body.contains("Content-Range: bytes 0-0/10\r\n\r\n0\r\n"));
+        assert!(body.contains("Content-Range: bytes 9-9/10\r\n\r\n9\r\n"));
+    }
+
+    #[test]
+    fn test_apply_overlapping_ranges() {
+        let mut response = response::text(HttpStatus::StatusOK, "0123456789".to_string());
+        apply(&mut response, "bytes=0-2,1-3", None);
+        assert_eq!(response.status().to_code(), 206);
+        assert_eq!(response.header("Content-Range"), Some("bytes 0-3/10"));
+        assert_eq!(response.content.as_bytes(), Some(&b"0123"[..]));
+
+        let mut response = response::text(HttpStatus::StatusOK, "0123456789".to_string());
+        apply(&mut response, "bytes=0-,0-", None);
+        assert_eq!(response.status().to_code(), 200);
+        assert_eq!(response.content.size(), Some(10));
+    }
+
+    #[test]
+    fn test_apply_unsatisfiable() {
+        let mut response = response::text(HttpStatus::StatusOK, "0123456789".to_string());
+        apply(&mut response, "bytes=20-", None);
+        assert_eq!(response.status().to_code(), 416);
+        assert_eq!(response.header("Content-Range"), Some("bytes */10"));
+    }
+
+    #[test]
+    fn test_apply_if_range_mismatch() {
+        let mut response = response::text(HttpStatus::StatusOK, "0123456789".to_string());
+        response.set_header("ETag", "\"v2\"");
+        apply(&mut response, "bytes=0-1", Some("\"v1\""));
+        assert_eq!(response.status().to_code(), 200);
+        assert_eq!(response.content.size(), Some(10));
+    }
+}
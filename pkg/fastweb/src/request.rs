@@ -1,22 +1,31 @@
-use std::{collections::HashMap, fmt::Debug};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Display, Formatter},
+    sync::Arc,
+};
 
-use crate::http::{Encoding, Headers, HttpMethod};
+use crate::form::{self, FormData};
+use crate::http::{Encoding, Headers, HttpMethod, HttpVersion, Mime};
+use crate::multipart::{self, Multipart, MultipartError, MultipartLimits};
+use crate::session::Session;
+use crate::{cookie, negotiation, url};
 
-use super::{
-    CRLF, EMPTY, QUERY_PARAM_KEY_VALUE_SEPARATOR, QUERY_PARAM_SEPARATOR, QUERY_PARAM_START,
-};
+use super::{CRLF, EMPTY, QUERY_PARAM_START};
 
 #[derive(Debug, Clone)]
 pub struct Request {
     method: HttpMethod,
+    version: Option<HttpVersion>,
     path: String,
     qualified_path: String,
     query_params: HashMap<String, String>,
     path_params: HashMap<String, String>,
     headers: HashMap<String, String>,
-    body: String,
+    body: Vec<u8>,
     raw: String,
     encoding: Option<Encoding>,
+    session: Option<Session>,
+    multipart: Option<Arc<Multipart>>,
 }
 
 impl Request {
@@ -24,6 +33,12 @@ impl Request {
         &self.method
     }
 
+    /// Returns the HTTP version of the request line, `None` if it is missing
+    /// or not one the server speaks.
+    pub fn version(&self) -> Option<&HttpVersion> {
+        self.version.as_ref()
+    }
+
     pub fn path(&self) -> &str {
         &self.path
     }
@@ -32,14 +47,96 @@ impl Request {
         &self.qualified_path
     }
 
+    pub fn set_qualified_path(&mut self, qualified_path: String) {
+        self.qualified_path = qualified_path;
+    }
+
     pub fn headers(&self) -> &HashMap<String, String> {
         &self.headers
     }
 
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the cookies sent in the `Cookie` header.
+    pub fn cookies(&self) -> HashMap<String, String> {
+        self.header(Headers::Cookie.to_str())
+            .map(cookie::parse)
+            .unwrap_or_default()
+    }
+
+    pub fn cookie(&self, name: &str) -> Option<String> {
+        self.cookies().remove(name)
+    }
+
+    /// Returns the session of the request when sessions are enabled with
+    /// [`RouterBuilder::sessions`](crate::router::RouterBuilder::sessions).
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    pub(crate) fn set_session(&mut self, session: Session) {
+        self.session = Some(session);
+    }
+
+    /// Returns the parsed `Content-Type` of the request body, if any.
+    pub fn content_type(&self) -> Option<Mime> {
+        self.header(Headers::ContentType.to_str())
+            .and_then(Mime::parse)
+    }
+
+    /// Returns the fields of an `application/x-www-form-urlencoded` body.
+    pub fn form(&self) -> Result<FormData, String> {
+        form::parse(self)
+    }
+
+    /// Returns the parts of a `multipart/form-data` body. Bodies the server
+    /// parsed while reading them are returned as is, others are parsed from
+    /// the body with the default limits.
+    pub fn multipart(&self) -> Result<Arc<Multipart>, MultipartError> {
+        if let Some(multipart) = &self.multipart {
+            return Ok(multipart.clone());
+        }
+
+        let content_type = self.content_type().ok_or(MultipartError::NotMultipart)?;
+        let boundary = multipart::boundary(&content_type).ok_or(MultipartError::NotMultipart)?;
+        let limits = MultipartLimits::default();
+
+        multipart::parse(self.body_bytes(), boundary, &limits).map(Arc::new)
+    }
+
+    pub(crate) fn set_multipart(&mut self, multipart: Multipart) {
+        self.multipart = Some(Arc::new(multipart));
+    }
+
+    /// Returns the body as text, or an empty string if it is not valid UTF-8;
+    /// use [`Request::body_bytes`] for binary content.
     pub fn body(&self) -> &str {
+        std::str::from_utf8(&self.body).unwrap_or_default()
+    }
+
+    pub fn body_bytes(&self) -> &[u8] {
         &self.body
     }
 
+    pub fn set_body(&mut self, body: Vec<u8>) {
+        self.body = body;
+    }
+
+    pub fn set_header(&mut self, key: &str, value: &str) {
+        self.remove_header(key);
+        self.headers.insert(key.to_string(), value.to_string());
+    }
+
+    pub fn remove_header(&mut self, key: &str) {
+        self.headers
+            .retain(|name, _| !name.eq_ignore_ascii_case(key));
+    }
+
     pub fn query_params(&self) -> &HashMap<String, String> {
         &self.query_params
     }
@@ -61,15 +158,83 @@ impl Request {
     }
 }
 
+#[derive(Debug, PartialEq)]
+pub enum ParseError {
+    /// The request line is not `method target [version]`.
+    Malformed(String),
+    /// A method the server does not implement.
+    UnknownMethod(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::Malformed(line) => write!(f, "Malformed request line: {:?}", line),
+            ParseError::UnknownMethod(method) => write!(f, "Unknown method: {}", method),
+        }
+    }
+}
+
+/// Returns the request without the empty lines a client may send before the
+/// request line, e.g. after the body of a previous request (RFC 9112 section 2.2).
+pub(crate) fn skip_empty_lines(request_raw: &[u8]) -> &[u8] {
+    let start = request_raw
+        .iter()
+        .position(|b| *b != b'\r' && *b != b'\n')
+        .unwrap_or(request_raw.len());
+    &request_raw[start..]
+}
+
 pub fn parse(request_raw: &str) -> Request {
-    let mut lines = request_raw.split(CRLF);
+    parse_bytes(request_raw.as_bytes())
+}
+
+/// Parses a raw request whose body may be binary. The request line and
+/// headers must be text.
+///
+/// # Panics
+///
+/// Panics if the request line is malformed, see [`try_parse_bytes`].
+pub fn parse_bytes(request_raw: &[u8]) -> Request {
+    match try_parse_bytes(request_raw) {
+        Ok(request) => request,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Parses a raw request like [`parse_bytes`], returning an error instead of
+/// panicking if the request line is malformed or names an unknown method.
+pub fn try_parse_bytes(request_raw: &[u8]) -> Result<Request, ParseError> {
+    let request_raw = skip_empty_lines(request_raw);
+    let separator = format!("{}{}", CRLF, CRLF);
+    let (head, body) = match request_raw
+        .windows(separator.len())
+        .position(|window| window == separator.as_bytes())
+    {
+        Some(index) => (
+            &request_raw[..index],
+            &request_raw[index + separator.len()..],
+        ),
+        None => (request_raw, &request_raw[request_raw.len()..]),
+    };
+
+    let head = String::from_utf8_lossy(head);
+    let mut lines = head.split(CRLF);
 
     // Parse the request line
-    let request_meta = lines.next().unwrap();
+    let request_meta = lines.next().unwrap_or_default();
     let mut request_meta_parts = request_meta.split_whitespace();
-    let method = request_meta_parts.next().unwrap();
-    let path = request_meta_parts.next().unwrap();
-    let _ = request_meta_parts.next(); // Ignore the HTTP version
+    let malformed = || ParseError::Malformed(request_meta.to_string());
+    let method = request_meta_parts.next().ok_or_else(malformed)?;
+    let path = request_meta_parts.next().ok_or_else(malformed)?;
+    let version = request_meta_parts.next().and_then(HttpVersion::from_str);
+
+    if request_meta_parts.next().is_some() {
+        return Err(malformed());
+    }
+
+    let method =
+        HttpMethod::from_str(method).ok_or(ParseError::UnknownMethod(method.to_string()))?;
 
     // Parse the headers
     let headers: HashMap<String, String> = lines
@@ -84,52 +249,32 @@ pub fn parse(request_raw: &str) -> Request {
         .collect();
 
     let mut path_parts = path.split(QUERY_PARAM_START);
-    let qualified_path = path_parts.nth(0).unwrap();
-    let query_params_str = path_parts.nth(0).unwrap_or_default();
-
-    let mut query_params = HashMap::new();
-
-    if !query_params_str.is_empty() {
-        query_params = query_params_str
-            .split(QUERY_PARAM_SEPARATOR)
-            .map(|param| {
-                let mut param_parts = param.split(QUERY_PARAM_KEY_VALUE_SEPARATOR);
-                let key = param_parts.next().unwrap().to_string();
-                let value = param_parts.next().unwrap_or(EMPTY).to_string();
-                (key, value)
-            })
-            .collect();
-    }
-
-    // Parse the body
-    let body = lines
-        .collect::<Vec<&str>>()
-        .join(CRLF)
-        .trim_matches(char::from(0))
-        .to_string();
-
-    let encoding = headers
-        .get(Headers::AcceptEncoding.to_str())
-        .and_then(|encoding| {
-            let supported = Encoding::get_supported();
-            let accepted_encodings = encoding.split(',').map(|e| e.trim());
-            for accepted_encoding in accepted_encodings {
-                if supported.contains(&Encoding::from_str(accepted_encoding).unwrap()) {
-                    return Some(Encoding::from_str(accepted_encoding).unwrap_or_default());
-                }
-            }
-            None
-        });
+    let qualified_path = path_parts.next().unwrap();
+    let query_params_str = path_parts.next().unwrap_or_default();
+
+    let query_params = url::parse_pairs(query_params_str).into_iter().collect();
+
     // Construct and return the Request
-    return Request {
-        method: HttpMethod::from_str(method).unwrap(),
+    let mut request = Request {
+        method,
+        version,
         path: path.to_string(),
         qualified_path: qualified_path.to_string(),
         headers,
-        body,
+        body: body.to_vec(),
         query_params,
         path_params: HashMap::new(),
-        raw: request_raw.to_string(),
-        encoding,
+        raw: String::from_utf8_lossy(request_raw).to_string(),
+        encoding: None,
+        session: None,
+        multipart: None,
     };
+
+    request.encoding = negotiation::encoding(&request);
+
+    Ok(request)
 }
+
+#[cfg(test)]
+#[path = "./request_test.rs"]
+mod test;
@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{parse, try_parse_bytes, ParseError};
+    use crate::http::HttpVersion;
+
+    #[test]
+    fn test_content_type() {
+        let request = parse(
+            "POST /upload HTTP/1.1\r\ncontent-type: application/json; charset=utf-8\r\n\r\n{}",
+        );
+        let mime = request.content_type().unwrap();
+        assert_eq!(mime.essence(), "application/json");
+        assert_eq!(mime.charset(), Some("utf-8"));
+    }
+
+    #[test]
+    fn test_content_type_missing() {
+        let request = parse("GET / HTTP/1.1\r\n\r\n");
+        assert!(request.content_type().is_none());
+    }
+
+    #[test]
+    fn test_query_params_decoded() {
+        let request = parse("GET /search?q=hello+world&lang=caf%C3%A9 HTTP/1.1\r\n\r\n");
+        assert_eq!(request.query_params()["q"], "hello world");
+        assert_eq!(request.query_params()["lang"], "café");
+    }
+
+    #[test]
+    fn test_version() {
+        assert_eq!(
+            parse("GET / HTTP/1.0\r\n\r\n").version(),
+            Some(&HttpVersion::Http10)
+        );
+        assert_eq!(
+            parse("GET / HTTP/1.1\r\n\r\n").version(),
+            Some(&HttpVersion::Http11)
+        );
+        assert_eq!(parse("GET / HTTP/2.0\r\n\r\n").version(), None);
+        assert_eq!(parse("GET /\r\n\r\n").version(), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            try_parse_bytes(b"OPTIONS / HTTP/1.1\r\n\r\n").unwrap_err(),
+            ParseError::UnknownMethod("OPTIONS".to_string())
+        );
+        assert!(matches!(
+            try_parse_bytes(b"GET\r\n\r\n"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            try_parse_bytes(b"GET / HTTP/1.1 extra\r\n\r\n"),
+            Err(ParseError::Malformed(_))
+        ));
+        assert!(matches!(
+            try_parse_bytes(b""),
+            Err(ParseError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_leading_empty_lines() {
+        let request = parse("\r\n\r\nGET /ping HTTP/1.1\r\n\r\n");
+        assert_eq!(request.path(), "/ping");
+    }
+}
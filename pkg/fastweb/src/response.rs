@@ -1,39 +1,85 @@
-use crate::http::{Encoding, HttpContentType, HttpStatus};
+use crate::body::Body;
+use crate::compression;
+use crate::conditional;
+use crate::cookie::Cookie;
+use crate::http::{date, Encoding, Headers, HttpContentType, HttpStatus, HttpVersion};
 use crate::CRLF;
 use badserde::json::Serde;
-use flate2::{write::GzEncoder, Compression};
-use logger::debug;
-use std::io::Write;
+use flate2::Compression;
+use logger::{debug, error};
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
 
 // #[derive(Default)]
 pub struct Response {
     pub status: HttpStatus,
     headers: Vec<String>,
-    pub content: String,
+    pub content: Body,
     pub content_type: HttpContentType,
     encoding: Option<Encoding>,
+    compression_level: u32,
+    compressible: bool,
+    trailers: Vec<String>,
+    version: HttpVersion,
 }
 
 pub fn new(status: HttpStatus, content: String, content_type: HttpContentType) -> Response {
     Response {
         status,
         headers: Vec::new(),
-        content,
+        content: Body::Bytes(content.into_bytes()),
         content_type,
         encoding: None,
+        compression_level: Compression::default().level(),
+        compressible: true,
+        trailers: Vec::new(),
+        version: HttpVersion::default(),
     }
 }
 
+/// Creates a response with binary content, e.g. images or pre-compressed data.
+pub fn bytes(status: HttpStatus, content: Vec<u8>, content_type: HttpContentType) -> Response {
+    let mut response = new(status, String::new(), content_type);
+    response.content = Body::Bytes(content);
+    response
+}
+
 pub fn html(status: HttpStatus, content: String) -> Response {
-    return new(status, content, HttpContentType::HTML);
+    new(status, content, HttpContentType::HTML)
 }
 
-pub fn json<T: Serde>(status: HttpStatus, content: T ) -> Response {
-    return new(status, content.to_json(), HttpContentType::JSON);
+pub fn json<T: Serde>(status: HttpStatus, content: T) -> Response {
+    new(status, content.to_json(), HttpContentType::JSON)
 }
 
 pub fn text(status: HttpStatus, content: String) -> Response {
-    return new(status, content, HttpContentType::TEXT);
+    new(status, content, HttpContentType::TEXT)
+}
+
+/// Creates a response whose content is produced chunk by chunk while it is sent.
+pub fn stream<I>(status: HttpStatus, chunks: I, content_type: HttpContentType) -> Response
+where
+    I: Iterator<Item = Vec<u8>> + Send + 'static,
+{
+    let mut response = new(status, String::new(), content_type);
+    response.content = Body::stream(chunks);
+    response
+}
+
+/// Creates a response streaming the content of `reader` until EOF.
+pub fn reader<R>(status: HttpStatus, reader: R, content_type: HttpContentType) -> Response
+where
+    R: Read + Send + 'static,
+{
+    let mut response = new(status, String::new(), content_type);
+    response.content = Body::reader(reader);
+    response
+}
+
+pub fn redirect(status: HttpStatus, location: &str) -> Response {
+    let mut response = new(status, String::new(), HttpContentType::HTML);
+    response.set_header(Headers::Location.to_str(), location);
+    response
 }
 
 impl Response {
@@ -42,48 +88,143 @@ impl Response {
     }
 
     fn protocol(&self) -> &str {
-        return "HTTP/1.1";
+        self.version.to_str()
+    }
+
+    pub fn version(&self) -> &HttpVersion {
+        &self.version
+    }
+
+    /// Sets the version of the status line, the server mirrors the one of the
+    /// request. HTTP/1.0 responses stream their content without chunks.
+    pub fn set_version(&mut self, version: HttpVersion) {
+        self.version = version;
+    }
+
+    pub fn header(&self, key: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .filter_map(|header| header.split_once(": "))
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
     }
 
-    fn set_header(&mut self, key: &str, value: &str) {
+    pub fn set_header(&mut self, key: &str, value: &str) {
         debug!("Setting Response Header: {} : {}", key, value);
         self.headers.push(format!("{}: {}", key, value));
     }
 
+    /// Adds a `Set-Cookie` header, once per cookie.
+    pub fn set_cookie(&mut self, cookie: &Cookie) {
+        self.set_header(Headers::SetCookie.to_str(), &cookie.to_string());
+    }
+
+    /// Adds a trailer field, sent after the content of streamed responses.
+    pub fn set_trailer(&mut self, key: &str, value: &str) {
+        self.trailers.push(format!("{}: {}", key, value));
+    }
+
+    pub fn is_compressible(&self) -> bool {
+        self.compressible
+    }
+
+    /// Opts the response out of compression, e.g. for already compressed data.
+    pub fn set_compressible(&mut self, compressible: bool) {
+        self.compressible = compressible;
+    }
+
+    pub fn set_compression_level(&mut self, level: u32) {
+        self.compression_level = level;
+    }
+
+    /// Sets an `ETag` computed from the content. Weak tags suit content that
+    /// is semantically but not byte-for-byte equivalent between requests.
+    /// Streamed and file bodies are left untouched.
+    pub fn set_etag(&mut self, weak: bool) {
+        if let Some(content) = self.content.as_bytes() {
+            let etag = conditional::etag(content, weak);
+            self.set_header(Headers::ETag.to_str(), &etag);
+        }
+    }
+
     pub fn set_encoding(&mut self, encoding: &Option<Encoding>) {
-        self.encoding = encoding.clone();
-        match encoding {
-            Some(e) => self.set_header("Content-Encoding", e.to_str()),
-            None => (),
+        // File bodies are sent as is, compressing them would mean reading them into memory
+        if let Body::File(_) = self.content {
+            return;
+        }
+
+        self.encoding = match encoding {
+            Some(Encoding::Identity) => None,
+            encoding => encoding.clone(),
+        };
+
+        if let Some(e) = self.encoding.clone() {
+            self.set_header("Content-Encoding", e.to_str());
+
+            // A strong tag identifies the exact bytes, which compression changes
+            if let Some(etag) = self.header(Headers::ETag.to_str()) {
+                if !etag.starts_with("W/") {
+                    let weak = format!("W/{}", etag);
+                    self.headers
+                        .retain(|header| !header.starts_with(Headers::ETag.to_str()));
+                    self.set_header(Headers::ETag.to_str(), &weak);
+                }
+            }
         }
     }
 
-    pub fn build(&mut self) -> Vec<u8> {
+    // Compresses the content if an encoding was negotiated and returns the
+    // status line and headers.
+    fn prepare(&mut self) -> Vec<u8> {
         debug!("Encoding set to: {:?}", self.encoding);
 
-        let (content, headers) = {
-            let content = match &self.encoding {
-                Some(enc) => match enc {
-                    Encoding::GZIP => {
-                        debug!("Compressing response content with GZIP");
-                        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-                        encoder.write_all(self.content.as_bytes()).unwrap();
-                        encoder.finish().unwrap()
-                    }
-                    _ => self.content.clone().into_bytes(),
-                },
-                None => self.content.clone().into_bytes(),
-            };
-
-            // Prepare headers
-            self.headers
-                .push(format!("Content-Type: {}", self.content_type.to_str()));
-            self.headers
-                .push(format!("Content-Length: {}", content.len()));
-            let headers = self.headers.join(CRLF);
-
-            (content, headers)
-        };
+        if let (Some(encoding), Body::Bytes(content)) = (&self.encoding, &self.content) {
+            debug!("Compressing response content with {}", encoding.to_str());
+            self.content = Body::Bytes(
+                compression::compress(encoding, self.compression_level, content).unwrap(),
+            );
+        }
+
+        if self.header(Headers::Date.to_str()).is_none() {
+            self.set_header(Headers::Date.to_str(), &date::now());
+        }
+
+        // A 304 describes the selected representation without sending it
+        if let HttpStatus::StatusNotModified = self.status {
+            self.content = Body::Bytes(Vec::new());
+            return self.head();
+        }
+
+        // Prepare headers
+        self.headers
+            .push(format!("Content-Type: {}", self.content_type.to_str()));
+        match self.content.size() {
+            Some(length) => self.headers.push(format!("Content-Length: {}", length)),
+            // Sent until the connection closes
+            None if self.version == HttpVersion::Http10 => {}
+            None => {
+                self.headers
+                    .push(format!("{}: chunked", Headers::TransferEncoding.to_str()));
+
+                if !self.trailers.is_empty() {
+                    let names = self
+                        .trailers
+                        .iter()
+                        .filter_map(|trailer| trailer.split_once(": "))
+                        .map(|(name, _)| name)
+                        .collect::<Vec<&str>>()
+                        .join(", ");
+                    self.headers
+                        .push(format!("{}: {}", Headers::Trailer.to_str(), names));
+                }
+            }
+        }
+        self.head()
+    }
+
+    // Formats the status line and headers.
+    fn head(&self) -> Vec<u8> {
+        let headers = self.headers.join(CRLF);
 
         let resp = format!(
             "{} {} {}{}{}{}{}",
@@ -96,11 +237,50 @@ impl Response {
             CRLF
         );
 
-        debug!("Response Raw\n{:?} {}", resp, self.content);
+        debug!("Response Raw\n{:?}", resp);
 
-        let mut resp = resp.into_bytes();
-        resp.extend(&content);
+        resp.into_bytes()
+    }
 
-        return resp;
+    /// Builds the complete response in memory, reading file and streamed
+    /// bodies if needed.
+    pub fn build(&mut self) -> Vec<u8> {
+        let content = std::mem::replace(&mut self.content, Body::Bytes(Vec::new()));
+        self.content = match content.into_bytes() {
+            Ok(content) => Body::Bytes(content),
+            // Nothing was sent yet, so the client can still be told
+            Err(e) => {
+                error!("Failed to read the response body: {}", e);
+                self.status = HttpStatus::StatusInternalServerError;
+                self.content_type = HttpContentType::TEXT;
+                Body::Bytes(self.status.to_str().as_bytes().to_vec())
+            }
+        };
+
+        let mut resp = self.prepare();
+        if let Body::Bytes(content) = &self.content {
+            resp.extend(content);
+        }
+        resp
+    }
+
+    /// Writes the response to the stream, sending file bodies with sendfile(2)
+    /// where available instead of copying them through memory.
+    pub fn write_to(&mut self, stream: &mut TcpStream) -> io::Result<()> {
+        let head = self.prepare();
+        stream.write_all(&head)?;
+
+        self.content.write_to(
+            stream,
+            self.encoding.as_ref().map(|e| (e, self.compression_level)),
+            match self.version {
+                HttpVersion::Http10 => None,
+                HttpVersion::Http11 => Some(&self.trailers),
+            },
+        )
     }
 }
+
+#[cfg(test)]
+#[path = "./response_test.rs"]
+mod test;
@@ -0,0 +1,58 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use super::super::{bytes, text};
+    use crate::body::Body;
+    use crate::http::{Encoding, HttpStatus};
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk gone"))
+        }
+    }
+
+    #[test]
+    fn test_bytes_response() {
+        let content = vec![0x89, b'P', b'N', b'G', 0x00, 0xff];
+        let mut response = bytes(HttpStatus::StatusOK, content.clone(), "image/png".into());
+        let raw = response.build();
+
+        assert!(raw.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(raw.ends_with(&content));
+        let head = String::from_utf8_lossy(&raw[..raw.len() - content.len()]).to_string();
+        assert!(head.contains("Content-Type: image/png\r\n"));
+        assert!(head.contains("Content-Length: 6\r\n"));
+    }
+
+    #[test]
+    fn test_gzip_response() {
+        let mut response = text(HttpStatus::StatusOK, "pong".to_string());
+        response.set_encoding(&Some(Encoding::GZIP));
+        let raw = response.build();
+        let head = String::from_utf8_lossy(&raw).to_string();
+
+        assert!(head.contains("Content-Encoding: gzip\r\n"));
+        assert!(raw.windows(2).any(|w| w == [0x1f, 0x8b]));
+    }
+
+    #[test]
+    fn test_date_header() {
+        let mut response = text(HttpStatus::StatusOK, "pong".to_string());
+        let raw = String::from_utf8(response.build()).unwrap();
+        assert!(raw.contains("\r\nDate: "));
+        assert!(raw.contains(" GMT\r\n"));
+    }
+
+    #[test]
+    fn test_build_read_error() {
+        let mut response = text(HttpStatus::StatusOK, String::new());
+        response.content = Body::reader(FailingReader);
+        let raw = String::from_utf8(response.build()).unwrap();
+
+        assert!(raw.starts_with("HTTP/1.1 500 Internal Server Error\r\n"));
+        assert!(raw.ends_with("Internal Server Error"));
+    }
+}
@@ -3,30 +3,64 @@ use std::{
     error::Error,
     fmt::{Debug, Display},
     fs,
-    io::prelude::*,
+    io::{self, prelude::*},
     net::{TcpListener, TcpStream},
+    path::PathBuf,
     sync::Arc,
+    time::{Duration, Instant},
 };
 
-use logger::{debug, error, info};
+use logger::{debug, error, info, warn};
 use workers::ThreadPool;
 
 use crate::{
-    http::{HttpContentType, HttpMethod, HttpStatus},
-    request,
+    compression::{self, CompressionPolicy, DecompressError},
+    conditional, files,
+    http::{Encoding, Headers, HttpContentType, HttpMethod, HttpStatus, HttpVersion},
+    multipart::{self, MultipartError, MultipartLimits},
+    range,
+    request::{self, ParseError},
     response::{self, Response},
-    Configuration
+    server::{self, Connections, Server, ServerHandle, Tracked},
+    session::Sessions,
+    stats::ServerStats,
+    url, Configuration,
 };
-use crate::{DOUBLE_PATH_SEPARATOR, EMPTY, LEFT_BRACKET, PATH_SEPARATOR, RIGHT_BRACKET};
+use crate::{
+    CRLF, EMPTY, LEFT_BRACKET, PATH_SEPARATOR, QUERY_PARAM_START, RIGHT_BRACKET, WILDCARD,
+};
+
+// Room for the method, spaces and version around the target of a request line.
+static REQUEST_LINE_OVERHEAD: usize = 32;
 
 type HandlerFunc = Arc<dyn Fn(request::Request) -> response::Response + Send + Sync + 'static>;
 
+// Returns the parameter name of a `{name}` or `{*name}` route segment.
+fn param_name(segment: &str) -> Option<String> {
+    if !(segment.starts_with(LEFT_BRACKET) && segment.ends_with(RIGHT_BRACKET)) {
+        return None;
+    }
+
+    let name = segment
+        .replace(LEFT_BRACKET, EMPTY)
+        .replace(RIGHT_BRACKET, EMPTY)
+        .replace(WILDCARD, EMPTY)
+        .to_lowercase();
+
+    Some(name)
+}
+
+// A `{*name}` segment captures the rest of the path, including separators.
+fn is_wildcard(segment: &str) -> bool {
+    segment.starts_with(&format!("{}{}", LEFT_BRACKET, WILDCARD))
+}
+
 pub struct RouteTable(pub Vec<(String, HttpMethod, Route)>);
 
 impl Display for RouteTable {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.iter().for_each(|(path, method, route)| {
-            write!(f, "{} {} {:?}\n", path, method, route).unwrap();
+            writeln!(f, "{} {} {:?}", path, method, route).unwrap();
         });
         Ok(())
     }
@@ -55,21 +89,25 @@ impl RouteTable {
                     return false;
                 }
 
-                if req_segments.len() != route.segments.len() {
+                if route.is_wildcard() {
+                    if req_segments.len() < route.segments.len() - 1 {
+                        return false;
+                    }
+                } else if req_segments.len() != route.segments.len() {
                     return false;
                 }
 
                 for (index, segment) in route.segments.iter().enumerate() {
-                    if segment.starts_with(LEFT_BRACKET) && segment.ends_with(RIGHT_BRACKET) {
+                    if param_name(segment).is_some() {
                         continue;
                     }
 
-                    if segment != &req_segments[index] {
+                    if req_segments.get(index) != Some(&segment.as_str()) {
                         return false;
                     }
                 }
 
-                return true;
+                true
             })
             .map(|(_, _, route)| route)
             .collect::<Vec<&Route>>();
@@ -78,7 +116,43 @@ impl RouteTable {
             return Some(matches[0]);
         }
 
-        return None;
+        None
+    }
+
+    pub fn url_for(&self, name: &str, params: &[(&str, &str)]) -> Result<String, String> {
+        let route = self
+            .0
+            .iter()
+            .map(|(_, _, route)| route)
+            .find(|route| route.name.as_deref() == Some(name))
+            .ok_or(format!("No route named '{}'", name))?;
+
+        let segments = route
+            .segments
+            .iter()
+            .map(|segment| {
+                let param = match param_name(segment) {
+                    Some(param) => param,
+                    None => return Ok(segment.clone()),
+                };
+
+                match params.iter().find(|(key, _)| key.to_lowercase() == param) {
+                    Some((_, value)) if is_wildcard(segment) => Ok(value
+                        .split(PATH_SEPARATOR)
+                        .map(url::encode)
+                        .collect::<Vec<String>>()
+                        .join(PATH_SEPARATOR)),
+                    Some((_, value)) if !value.is_empty() => Ok(url::encode(value)),
+                    Some(_) => Err(format!("Empty parameter '{}' for route '{}'", param, name)),
+                    None => Err(format!(
+                        "Missing parameter '{}' for route '{}'",
+                        param, name
+                    )),
+                }
+            })
+            .collect::<Result<Vec<String>, String>>()?;
+
+        Ok(segments.join(PATH_SEPARATOR))
     }
 
     pub fn insert(&mut self, route: Route) {
@@ -88,6 +162,9 @@ impl RouteTable {
 }
 
 pub struct Route {
+    name: Option<String>,
+    compress: bool,
+    max_body_size: Option<u64>,
     method: HttpMethod,
     path: String,
     base_path: String,
@@ -100,6 +177,9 @@ pub struct Route {
 impl Clone for Route {
     fn clone(&self) -> Self {
         Route {
+            name: self.name.clone(),
+            compress: self.compress,
+            max_body_size: self.max_body_size,
             method: self.method.clone(),
             path: self.path.clone(),
             base_path: self.base_path.clone(),
@@ -111,6 +191,14 @@ impl Clone for Route {
     }
 }
 
+impl Route {
+    fn is_wildcard(&self) -> bool {
+        self.segments
+            .last()
+            .is_some_and(|segment| is_wildcard(segment))
+    }
+}
+
 impl Debug for Route {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{} {}", self.method, self.path)
@@ -123,9 +211,52 @@ impl Display for Route {
     }
 }
 
+/// Policy for request paths that are not in canonical form, e.g. `/ping/`,
+/// `/a//b` or `/a/./b` (see [`url::normalize`]).
+#[derive(Clone)]
+pub enum PathNormalization {
+    /// Match the path exactly as received.
+    Strict,
+    /// Redirect to the canonical path using the given status (301 or 308).
+    Redirect(HttpStatus),
+    /// Match against the canonical path without redirecting.
+    Lenient,
+}
+
+/// What happens to connections accepted while too many are already waiting
+/// for a worker (see [`RouterBuilder::max_queued_connections`]).
+#[derive(Clone)]
+pub enum LoadShedding {
+    /// Answer 503 with a `Retry-After` of the given duration, in seconds.
+    Reject(Duration),
+    /// Close the connection without a response.
+    Close,
+}
+
 pub struct RouterBuilder {
     pub configuration: Configuration,
     pub routes: RouteTable,
+    pub(crate) route_options: HashMap<String, RouteOptions>,
+}
+
+// Options set for the routes registered with a path. They are kept apart from
+// the routes and applied when the route table is built, so they take effect
+// whether the route is added before or after them.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct RouteOptions {
+    disable_compression: bool,
+    max_body_size: Option<u64>,
+}
+
+impl RouteOptions {
+    fn apply(&self, route: &mut Route) {
+        if self.disable_compression {
+            route.compress = false;
+        }
+        if let Some(max_body_size) = self.max_body_size {
+            route.max_body_size = Some(max_body_size);
+        }
+    }
 }
 
 impl RouterBuilder {
@@ -154,48 +285,198 @@ impl RouterBuilder {
         self
     }
 
-    fn get_bind_address(&self) -> String {
-        format!("{}:{}", self.configuration.host, self.configuration.port)
+    pub fn path_normalization(&mut self, policy: PathNormalization) -> &mut Self {
+        if let PathNormalization::Redirect(status) = &policy {
+            assert!(
+                matches!(
+                    status,
+                    HttpStatus::StatusMovedPermanently | HttpStatus::StatusPermanentRedirect
+                ),
+                "Path normalization redirects must use 301 or 308"
+            );
+        }
+
+        self.configuration.path_normalization = policy;
+        self
     }
 
-    pub fn serve(&self) -> Result<(), Box<dyn Error>> {
-        info!(
-            "Starting server on {}:{}",
-            self.configuration.host, self.configuration.port
-        );
+    pub fn compression(&mut self, policy: CompressionPolicy) -> &mut Self {
+        self.configuration.compression = policy;
+        self
+    }
 
-        let listener = TcpListener::bind(self.get_bind_address())?;
+    /// Limits for `multipart/form-data` bodies, which are parsed while they
+    /// are read.
+    pub fn multipart_limits(&mut self, limits: MultipartLimits) -> &mut Self {
+        self.configuration.multipart = limits;
+        self
+    }
 
-        let pool = ThreadPool::new(self.configuration.workers);
+    /// Limits the size of compressed request bodies once decompressed.
+    pub fn max_decompressed_size(&mut self, max_decompressed_size: u64) -> &mut Self {
+        self.configuration.max_decompressed_size = max_decompressed_size;
+        self
+    }
 
-        let routes = Arc::new(self.get_routes());
+    /// Sets the `Server` header sent with every response, `None` to omit it.
+    pub fn server_header(&mut self, server: Option<&str>) -> &mut Self {
+        self.configuration.server = server.map(String::from);
+        self
+    }
+
+    /// Enables sessions, available to handlers through `Request::session`.
+    pub fn sessions(&mut self, sessions: Sessions) -> &mut Self {
+        self.configuration.sessions = Some(Arc::new(sessions));
+        self
+    }
+
+    /// Never compresses responses of the routes registered with this path,
+    /// or of the files served under this prefix.
+    pub fn disable_compression(&mut self, path: &str) -> &mut Self {
+        self.options_at(path).disable_compression = true;
+        self
+    }
+
+    /// Longest request target accepted, longer ones are answered with 414.
+    pub fn max_uri_length(&mut self, max_uri_length: usize) -> &mut Self {
+        self.configuration.max_uri_length = max_uri_length;
+        self
+    }
+
+    /// Most bytes of header fields accepted, more are answered with 431.
+    pub fn max_header_bytes(&mut self, max_header_bytes: usize) -> &mut Self {
+        self.configuration.max_header_bytes = max_header_bytes;
+        self
+    }
+
+    /// Most header fields accepted, more are answered with 431.
+    pub fn max_header_count(&mut self, max_header_count: usize) -> &mut Self {
+        self.configuration.max_header_count = max_header_count;
+        self
+    }
+
+    /// Longest time a client may take to send the request line and headers,
+    /// however it spreads them out, before being answered with 408.
+    pub fn header_read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.configuration.header_read_timeout = timeout;
+        self
+    }
+
+    /// Longest time a client may take to send the request body before being
+    /// answered with 408.
+    pub fn body_read_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.configuration.body_read_timeout = timeout;
+        self
+    }
+
+    /// Longest a single write of the response may block on a client that
+    /// stopped reading, the connection is dropped after it.
+    pub fn write_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.configuration.write_timeout = timeout;
+        self
+    }
+
+    /// Most accepted connections waiting for a free worker, further ones are
+    /// shed according to [`RouterBuilder::load_shedding`]. Unbounded by default.
+    pub fn max_queued_connections(&mut self, max_queued_connections: usize) -> &mut Self {
+        self.configuration.max_queued_connections = Some(max_queued_connections);
+        self
+    }
+
+    pub fn load_shedding(&mut self, policy: LoadShedding) -> &mut Self {
+        self.configuration.load_shedding = policy;
+        self
+    }
+
+    /// How long in-flight requests may take to finish once SIGINT or SIGTERM
+    /// was received, connections still open afterwards are closed.
+    pub fn drain_timeout(&mut self, drain_timeout: Duration) -> &mut Self {
+        self.configuration.drain_timeout = drain_timeout;
+        self
+    }
+
+    /// Whether SIGINT and SIGTERM shut the server down gracefully, disabled by
+    /// default. Enabling it replaces the handlers the application installed
+    /// for these signals.
+    pub fn handle_signals(&mut self, handle_signals: bool) -> &mut Self {
+        self.configuration.handle_signals = handle_signals;
+        self
+    }
+
+    /// Counters of accepted and shed connections, updated while serving.
+    pub fn stats(&self) -> Arc<ServerStats> {
+        Arc::clone(&self.configuration.stats)
+    }
+
+    /// How long a new connection may wait before sending its request.
+    pub fn idle_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.configuration.idle_timeout = timeout;
+        self
+    }
+
+    /// Largest request body accepted, larger ones are answered with 413
+    /// before being read.
+    pub fn max_body_size(&mut self, max_body_size: u64) -> &mut Self {
+        self.configuration.max_body_size = max_body_size;
+        self
+    }
+
+    /// Overrides the body size limit for the routes registered with this path,
+    /// e.g. to allow large uploads on a single route.
+    pub fn route_max_body_size(&mut self, path: &str, max_body_size: u64) -> &mut Self {
+        self.options_at(path).max_body_size = Some(max_body_size);
+        self
+    }
+
+    fn options_at(&mut self, path: &str) -> &mut RouteOptions {
+        self.route_options.entry(url::normalize(path)).or_default()
+    }
+
+    fn get_bind_address(&self) -> String {
+        format!("{}:{}", self.configuration.host, self.configuration.port)
+    }
+
+    /// Binds the listener, `port(0)` picks a free port reported by
+    /// [`Server::local_addr`]. Nothing is served until the server is run.
+    pub fn bind(&self) -> Result<Server, Box<dyn Error>> {
+        let listener = TcpListener::bind(self.get_bind_address())?;
 
         info!("Registering routes..");
-        info!("\n{}", routes);
-
-        for stream in listener.incoming() {
-            let routes = Arc::clone(&routes);
-            let buffer_size = self.configuration.buffer_size;
-
-            match stream {
-                Ok(stream) => {
-                    pool.execute(move || {
-                        if let Err(e) = handle(stream, &routes, &buffer_size) {
-                            error!("Error handling request {}", e)
-                        }
-                    });
-                }
-                Err(e) => {
-                    return Err(Box::new(e));
-                }
-            }
-        }
+        info!("\n{}", self.routes);
 
-        return Ok(());
+        Ok(Server::new(
+            listener,
+            self.get_routes(),
+            self.configuration.clone(),
+        )?)
     }
 
-    pub fn add_route(&mut self, path: &str, method: HttpMethod, handler: HandlerFunc) -> &Self {
-        let sanitized_path = path.replace(DOUBLE_PATH_SEPARATOR, PATH_SEPARATOR);
+    /// Starts serving on a background thread. The server stops through the
+    /// returned handle or, if enabled, on SIGINT or SIGTERM.
+    pub fn serve(&self) -> Result<ServerHandle, Box<dyn Error>> {
+        return Ok(self.bind()?.spawn());
+    }
+
+    pub fn add_route(
+        &mut self,
+        path: &str,
+        method: HttpMethod,
+        handler: HandlerFunc,
+        name: Option<&str>,
+    ) -> &Self {
+        if let Some(name) = name {
+            assert!(
+                !self
+                    .routes
+                    .0
+                    .iter()
+                    .any(|(_, _, route)| route.name.as_deref() == Some(name)),
+                "Route name '{}' is already registered",
+                name
+            );
+        }
+
+        let sanitized_path = url::normalize(path);
 
         let tokens: Vec<String> = sanitized_path
             .split(PATH_SEPARATOR)
@@ -204,12 +485,13 @@ impl RouterBuilder {
 
         let mut path_params = vec![];
 
-        for token in &tokens {
-            if token.starts_with(LEFT_BRACKET) && token.ends_with(RIGHT_BRACKET) {
-                let param = token
-                    .replace(LEFT_BRACKET, EMPTY)
-                    .replace(RIGHT_BRACKET, EMPTY)
-                    .to_lowercase();
+        for (index, token) in tokens.iter().enumerate() {
+            if let Some(param) = param_name(token) {
+                assert!(
+                    !is_wildcard(token) || index == tokens.len() - 1,
+                    "Wildcard segment must be the last segment of '{}'",
+                    path
+                );
                 path_params.push(param);
             }
         }
@@ -221,10 +503,13 @@ impl RouterBuilder {
             .join(PATH_SEPARATOR);
 
         self.routes.insert(Route {
-            method: method,
+            name: name.map(|name| name.to_string()),
+            compress: true,
+            max_body_size: None,
+            method,
             path: sanitized_path.clone(),
-            base_path: base_path,
-            handler: handler,
+            base_path,
+            handler,
             path_params: match path_params.len() {
                 0 => None,
                 _ => Some(path_params),
@@ -233,94 +518,742 @@ impl RouterBuilder {
             segments: tokens,
         });
 
-        return self;
+        self
+    }
+
+    pub fn static_files(&mut self, prefix: &str, directory: &str) -> &Self {
+        let root = PathBuf::from(directory);
+        let path = format!(
+            "{}{}{}{}{}{}",
+            prefix,
+            PATH_SEPARATOR,
+            LEFT_BRACKET,
+            WILDCARD,
+            files::PATH_PARAM,
+            RIGHT_BRACKET
+        );
+
+        self.add_route(
+            &path,
+            HttpMethod::GET,
+            Arc::new(move |request| files::serve(&request, &root)),
+            None,
+        );
+
+        self
     }
 
     pub fn get(&mut self, path: &str, handler: HandlerFunc) -> &Self {
-        self.add_route(path, HttpMethod::GET, handler);
-        return self;
+        self.add_route(path, HttpMethod::GET, handler, None);
+        self
     }
 
     pub fn post(&mut self, path: &str, handler: HandlerFunc) -> &Self {
-        self.add_route(path, HttpMethod::POST, handler);
-        return self;
+        self.add_route(path, HttpMethod::POST, handler, None);
+        self
+    }
+
+    pub fn put(&mut self, path: &str, handler: HandlerFunc) -> &Self {
+        self.add_route(path, HttpMethod::PUT, handler, None);
+        self
     }
 
     pub fn get_routes(&self) -> RouteTable {
-        return self.routes.clone();
+        let mut routes = self.routes.clone();
+
+        for (_, _, route) in routes.0.iter_mut() {
+            // Options of a prefix first, so those of the exact path win
+            if route.is_wildcard() {
+                if let Some(options) = self.route_options.get(&route.base_path) {
+                    options.apply(route);
+                }
+            }
+            if let Some(options) = self.route_options.get(&route.path) {
+                options.apply(route);
+            }
+        }
+
+        routes
+    }
+}
+
+// Accepts connections and hands them to the workers until the server stops,
+// then drains them.
+pub(crate) fn accept(
+    listener: TcpListener,
+    routes: Arc<RouteTable>,
+    configuration: Arc<Configuration>,
+    connections: Arc<Connections>,
+) {
+    let pool = ThreadPool::new(configuration.workers);
+
+    for stream in listener.incoming() {
+        if connections.is_stopping() {
+            break;
+        }
+
+        let routes = Arc::clone(&routes);
+        let configuration = Arc::clone(&configuration);
+        let job_connections = Arc::clone(&connections);
+
+        match stream {
+            Ok(stream) => {
+                configuration.stats.record_accepted();
+
+                // Every worker is busy and enough connections wait already
+                let saturated = configuration
+                    .max_queued_connections
+                    .is_some_and(|max_queued| pool.pending() >= configuration.workers + max_queued);
+
+                if saturated {
+                    shed(stream, &configuration);
+                    continue;
+                }
+
+                pool.execute(move || {
+                    if let Err(e) = handle(stream, &routes, &configuration, &job_connections) {
+                        error!("Error handling request {}", e)
+                    }
+                });
+            }
+            Err(e) => {
+                error!("Failed to accept connection: {}", e);
+            }
+        }
+    }
+
+    server::drain(pool, &connections);
+}
+
+// Turns away a connection accepted while the workers are saturated. This
+// runs on the accepting thread, so it never waits on the client.
+fn shed(mut stream: TcpStream, configuration: &Configuration) {
+    configuration.stats.record_shed();
+    warn!(
+        "Shedding connection, {} shed so far",
+        configuration.stats.shed()
+    );
+
+    if let LoadShedding::Reject(retry_after) = &configuration.load_shedding {
+        let mut response = response::text(
+            HttpStatus::StatusServiceUnavailable,
+            "Server is overloaded".to_string(),
+        );
+        response.set_header(
+            Headers::RetryAfter.to_str(),
+            &retry_after.as_secs().to_string(),
+        );
+        response.set_header(Headers::Connection.to_str(), "close");
+
+        // The response fits in the socket buffer, a full one means the
+        // client is not worth waiting for
+        if stream.set_nonblocking(true).is_ok() {
+            let _ = stream.write_all(&response.build());
+        }
+    }
+}
+
+// A client connection and the bytes received past the request head, which
+// belong to the body.
+struct Connection<'a> {
+    stream: TcpStream,
+    pending: Vec<u8>,
+    tracked: Tracked<'a>,
+}
+
+// Reads from the stream until the deadline, however the reads are spread out,
+// so a client trickling bytes cannot hold a worker forever.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+
+        if remaining.is_zero() {
+            return Err(io::Error::from(io::ErrorKind::TimedOut));
+        }
+
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buffer)
     }
 }
 
+// Timed out reads fail with WouldBlock on Unix and TimedOut on Windows.
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    )
+}
+
+fn request_timeout() -> Response {
+    response::text(
+        HttpStatus::StatusRequestTimeout,
+        "Timed out reading the request".to_string(),
+    )
+}
+
 fn handle(
-    mut stream: TcpStream,
+    stream: TcpStream,
     routes: &RouteTable,
-    buffer_size: &usize,
+    configuration: &Configuration,
+    connections: &Connections,
 ) -> Result<(), Box<dyn Error>> {
-    debug!("Accepted connection from: {}", stream.peer_addr().unwrap());
+    // Queued behind requests that outlived the drain timeout
+    if connections.is_forced() {
+        return Ok(());
+    }
+
+    debug!("Accepted connection from: {}", stream.peer_addr()?);
+
+    stream.set_write_timeout(Some(configuration.write_timeout))?;
+
+    let mut connection = Connection {
+        tracked: connections.register(&stream)?,
+        stream,
+        pending: Vec::new(),
+    };
 
-    let mut buffer = vec![0; *buffer_size];
-    let bytes_read = stream.read(&mut buffer)?;
-    let raw_request = std::str::from_utf8(&buffer[..bytes_read])?;
+    serve_request(&mut connection, routes, configuration)
+}
 
-    let mut request = request::parse(raw_request);
+// Serves the request of the connection, which is closed after the response.
+fn serve_request(
+    connection: &mut Connection,
+    routes: &RouteTable,
+    configuration: &Configuration,
+) -> Result<(), Box<dyn Error>> {
+    let mut request = match read_head(connection, configuration) {
+        Ok(Some(request)) => request,
+        Ok(None) => return Ok(()),
+        Err(response) => {
+            reject(&mut connection.stream, response)?;
+            return Ok(());
+        }
+    };
 
     debug!("Parsed request\n{:?}", request);
 
+    let version = match request.version() {
+        Some(version) => version.clone(),
+        None => {
+            let response = response::text(
+                HttpStatus::StatusHTTPVersionNotSupported,
+                "Only HTTP/1.0 and HTTP/1.1 are supported".to_string(),
+            );
+            reject(&mut connection.stream, response)?;
+            return Ok(());
+        }
+    };
+
     let method = request.method().to_string();
     let path = request.path().to_string();
-    let qualified_path = request.qualified_path().to_string();
+    let enc = request.encoding();
+
+    let range = match request.method() {
+        HttpMethod::GET => request.header(Headers::Range.to_str()).map(String::from),
+        _ => None,
+    };
+    let if_range = request.header(Headers::IfRange.to_str()).map(String::from);
+    let preconditions = conditional::Preconditions::from_request(&request);
+
+    let mut response = match preprocess(connection, &mut request, routes, configuration) {
+        Some(response) => response,
+        None => match &configuration.sessions {
+            Some(sessions) => {
+                let session = sessions.start(&mut request);
+                let mut response = dispatch(request, routes);
+                sessions.finish(&session, &mut response);
+                response
+            }
+            None => dispatch(request, routes),
+        },
+    };
+
+    // Preconditions are evaluated before Range (RFC 9110 section 13.2.2)
+    preconditions.apply(&mut response);
 
+    if let Some(range) = range {
+        range::apply(&mut response, &range, if_range.as_deref());
+    }
+
+    configuration.compression.apply(&mut response, &enc);
+
+    // A handler may have named itself already
+    if let Some(server) = &configuration.server {
+        if response.header(Headers::Server.to_str()).is_none() {
+            response.set_header(Headers::Server.to_str(), server);
+        }
+    }
+
+    set_close(&mut response);
+    response.set_version(version);
+    response.write_to(&mut connection.stream)?;
+    info!("{} {} {}", method, path, response.status());
+    connection.stream.flush()?;
+
+    Ok(())
+}
+
+// Tells the client the connection closes after the response, unless the
+// handler set a Connection header itself.
+fn set_close(response: &mut Response) {
+    if response.header(Headers::Connection.to_str()).is_none() {
+        response.set_header(Headers::Connection.to_str(), "close");
+    }
+}
+
+// Answers a request that could not be read and closes the connection.
+fn reject(stream: &mut TcpStream, mut response: Response) -> io::Result<()> {
+    info!("Rejected request: {}", response.status());
+    set_close(&mut response);
+    response.write_to(stream)?;
+    stream.flush()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+// Reads until the end of the request head, returning `None` if the client
+// closed the connection, or let it idle past the idle timeout, without sending
+// anything. Bytes received past the head are kept for the body. A head over the configured limits is answered with 414 or
+// 431 without reading the rest, one not received in time with 408.
+fn read_head(
+    connection: &mut Connection,
+    configuration: &Configuration,
+) -> Result<Option<request::Request>, Response> {
+    let separator = format!("{}{}", CRLF, CRLF);
+    let mut data = Vec::new();
+    let mut buffer = vec![0; configuration.buffer_size];
+
+    // The connection idles until the request starts, the header timeout
+    // starts with its first byte
+    let header_deadline = || Instant::now() + configuration.header_read_timeout;
+    let mut deadline = None;
+
+    loop {
+        let end = find(&data, separator.as_bytes());
+
+        if let Some(response) = check_head(&data[..end.unwrap_or(data.len())], configuration) {
+            return Err(response);
+        }
+
+        if let Some(end) = end {
+            connection.pending = data.split_off(end + separator.len());
+            return match request::try_parse_bytes(&data) {
+                Ok(request) => Ok(Some(request)),
+                Err(e @ ParseError::UnknownMethod(_)) => Err(response::text(
+                    HttpStatus::StatusNotImplemented,
+                    e.to_string(),
+                )),
+                Err(e) => Err(response::text(HttpStatus::StatusBadRequest, e.to_string())),
+            };
+        }
+
+        let read = match deadline {
+            Some(deadline) => Deadline {
+                stream: &connection.stream,
+                deadline,
+            }
+            .read(&mut buffer),
+            None => {
+                // Closed right away once the server is stopping
+                if !connection.tracked.set_idle(true) {
+                    return Ok(None);
+                }
+                let read = connection
+                    .stream
+                    .set_read_timeout(Some(configuration.idle_timeout))
+                    .and_then(|_| connection.stream.read(&mut buffer));
+                connection.tracked.set_idle(false);
+                read
+            }
+        };
+
+        let read = match read {
+            Ok(read) => read,
+            Err(e) if is_timeout(&e) && deadline.is_none() => return Ok(None),
+            Err(e) if is_timeout(&e) => return Err(request_timeout()),
+            Err(_) if data.is_empty() => return Ok(None),
+            Err(e) => {
+                return Err(response::text(
+                    HttpStatus::StatusBadRequest,
+                    format!("Failed to read request: {}", e),
+                ))
+            }
+        };
+
+        if read == 0 {
+            if data.is_empty() {
+                return Ok(None);
+            }
+            return Err(response::text(
+                HttpStatus::StatusBadRequest,
+                "Connection closed inside the request head".to_string(),
+            ));
+        }
+
+        // Empty lines before the request line are ignored (RFC 9112 section 2.2)
+        match data.is_empty() {
+            true => data.extend_from_slice(request::skip_empty_lines(&buffer[..read])),
+            false => data.extend_from_slice(&buffer[..read]),
+        }
+
+        if !data.is_empty() {
+            deadline = deadline.or_else(|| Some(header_deadline()));
+        }
+    }
+}
+
+// Checks the request line and headers received so far against the limits.
+fn check_head(head: &[u8], configuration: &Configuration) -> Option<Response> {
+    let (line, headers) = match find(head, CRLF.as_bytes()) {
+        Some(index) => (&head[..index], Some(&head[index + CRLF.len()..])),
+        None => (head, None),
+    };
+
+    // Until the line is complete, all of it beyond the method and version
+    // could be the target
+    let uri_length = match headers {
+        Some(_) => line.split(|b| *b == b' ').nth(1).unwrap_or_default().len(),
+        None => line.len().saturating_sub(REQUEST_LINE_OVERHEAD),
+    };
+
+    if uri_length > configuration.max_uri_length {
+        return Some(response::text(
+            HttpStatus::StatusURITooLong,
+            format!("URI exceeds {} bytes", configuration.max_uri_length),
+        ));
+    }
+
+    let headers = headers.unwrap_or_default();
+    let count = headers
+        .split(|b| *b == b'\n')
+        .filter(|line| !line.is_empty() && *line != b"\r")
+        .count();
+
+    if headers.len() > configuration.max_header_bytes || count > configuration.max_header_count {
+        return Some(response::text(
+            HttpStatus::StatusRequestHeaderFieldsTooLarge,
+            format!(
+                "Headers exceed {} bytes or {} fields",
+                configuration.max_header_bytes, configuration.max_header_count
+            ),
+        ));
+    }
+
+    None
+}
+
+// Reads the rest of the body announced by Content-Length. Multipart bodies
+// are parsed as they arrive, so uploads are spooled to disk instead of being
+// held in memory.
+fn read_body(
+    connection: &mut Connection,
+    request: &mut request::Request,
+    limit: u64,
+    expects_continue: bool,
+    configuration: &Configuration,
+) -> Result<(), Response> {
+    let bad_request = |message: String| response::text(HttpStatus::StatusBadRequest, message);
+
+    let length = match request.header(Headers::ContentLength.to_str()) {
+        Some(length) => length
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| bad_request(format!("Invalid Content-Length: {}", length)))?,
+        None => return Ok(()),
+    };
+
+    if length > limit {
+        return Err(response::text(
+            HttpStatus::StatusContentTooLarge,
+            format!("Request body exceeds {} bytes", limit),
+        ));
+    }
+
+    let buffered = connection.pending.len().min(length as usize);
+    let received: Vec<u8> = connection.pending.drain(..buffered).collect();
+    let remaining = length - received.len() as u64;
+
+    if expects_continue && remaining > 0 {
+        debug!("Sending 100 Continue");
+        let status = HttpStatus::StatusContinue;
+        write!(
+            connection.stream,
+            "HTTP/1.1 {} {}{}{}",
+            status.to_code(),
+            status.to_str(),
+            CRLF,
+            CRLF
+        )
+        .map_err(|e| bad_request(format!("Failed to send 100 Continue: {}", e)))?;
+    }
+
+    let stream = Deadline {
+        stream: &connection.stream,
+        deadline: Instant::now() + configuration.body_read_timeout,
+    };
+    let mut body = io::Cursor::new(received).chain(stream.take(remaining));
+
+    // Compressed bodies are decoded before anything looks at their content
+    let boundary = request
+        .content_type()
+        .filter(|_| request.header(Headers::ContentEncoding.to_str()).is_none())
+        .and_then(|mime| multipart::boundary(&mime).map(String::from));
+
+    if let Some(boundary) = boundary {
+        return match multipart::parse(&mut body, &boundary, &configuration.multipart) {
+            Ok(multipart) => {
+                // Skip an epilogue after the closing boundary
+                io::copy(&mut body, &mut io::sink()).map_err(|e| match is_timeout(&e) {
+                    true => request_timeout(),
+                    false => bad_request(format!("Failed to read body: {}", e)),
+                })?;
+                request.set_multipart(multipart);
+                request.set_body(Vec::new());
+                Ok(())
+            }
+            Err(MultipartError::TooLarge) => Err(response::text(
+                HttpStatus::StatusContentTooLarge,
+                MultipartError::TooLarge.to_string(),
+            )),
+            Err(MultipartError::Io(e)) if is_timeout(&e) => Err(request_timeout()),
+            Err(e) => Err(bad_request(e.to_string())),
+        };
+    }
+
+    let mut content = Vec::new();
+    body.read_to_end(&mut content)
+        .map_err(|e| match is_timeout(&e) {
+            true => request_timeout(),
+            false => bad_request(format!("Failed to read body: {}", e)),
+        })?;
+
+    if (content.len() as u64) < length {
+        return Err(bad_request("Incomplete request body".to_string()));
+    }
+
+    request.set_body(content);
+    Ok(())
+}
+
+// Runs the steps that may answer the request before it reaches a handler.
+// The body is only read once the request is known to be routable.
+fn preprocess(
+    connection: &mut Connection,
+    request: &mut request::Request,
+    routes: &RouteTable,
+    configuration: &Configuration,
+) -> Option<Response> {
+    if let Some(response) = check_framing(request) {
+        return Some(response);
+    }
+
+    if let Some(redirect) = normalize_path(request, &configuration.path_normalization) {
+        return Some(redirect);
+    }
+
+    let expects_continue = match check_expectation(request) {
+        Ok(expects_continue) => expects_continue,
+        Err(response) => return Some(response),
+    };
+
+    let route = routes.find(request.qualified_path(), request.method());
+
+    // Spare the client from uploading a body nobody will read
+    if expects_continue && route.is_none() {
+        return Some(not_found());
+    }
+
+    let body_limit = route
+        .and_then(|route| route.max_body_size)
+        .unwrap_or(configuration.max_body_size);
+
+    if let Err(response) = read_body(
+        connection,
+        request,
+        body_limit,
+        expects_continue,
+        configuration,
+    ) {
+        return Some(response);
+    }
+
+    if let Err(response) = decode_body(request, configuration.max_decompressed_size) {
+        return Some(response);
+    }
+
+    None
+}
+
+// Only bodies framed by Content-Length are read. A Transfer-Encoding body is
+// refused with 501, and one framed both ways with 400 as it could be read
+// differently by a proxy in front (RFC 9112 section 6.3). Either way the
+// connection is closed, as the body is left unread.
+fn check_framing(request: &request::Request) -> Option<Response> {
+    let transfer_encoding = request.header(Headers::TransferEncoding.to_str())?;
+
+    if request.header(Headers::ContentLength.to_str()).is_some() {
+        return Some(response::text(
+            HttpStatus::StatusBadRequest,
+            "Both Content-Length and Transfer-Encoding are set".to_string(),
+        ));
+    }
+
+    Some(response::text(
+        HttpStatus::StatusNotImplemented,
+        format!("Unsupported Transfer-Encoding: {}", transfer_encoding),
+    ))
+}
+
+// Returns whether the client waits for `100 Continue` before sending the body
+// (RFC 9110 section 10.1.1). Any other expectation is answered with 417.
+// HTTP/1.0 clients cannot have meant one, so theirs are ignored.
+fn check_expectation(request: &request::Request) -> Result<bool, Response> {
+    if request.version() == Some(&HttpVersion::Http10) {
+        return Ok(false);
+    }
+
+    match request.header(Headers::Expect.to_str()) {
+        None => Ok(false),
+        Some(expect) if expect.trim().eq_ignore_ascii_case("100-continue") => Ok(true),
+        Some(expect) => Err(response::text(
+            HttpStatus::StatusExpectationFailed,
+            format!("Unsupported expectation: {}", expect),
+        )),
+    }
+}
+
+// Transparently decompresses a request body sent with a Content-Encoding.
+fn decode_body(request: &mut request::Request, limit: u64) -> Result<(), Response> {
+    let content_encoding = match request.header(Headers::ContentEncoding.to_str()) {
+        Some(content_encoding) => content_encoding.to_string(),
+        None => return Ok(()),
+    };
+
+    debug!("Decoding request body with {}", content_encoding);
+
+    let body = request.body_bytes().to_vec();
+
+    match compression::decompress(&content_encoding, body, limit) {
+        Ok(body) => {
+            request.remove_header(Headers::ContentEncoding.to_str());
+            request.set_header(Headers::ContentLength.to_str(), &body.len().to_string());
+            request.set_body(body);
+            Ok(())
+        }
+        Err(DecompressError::Unsupported(coding)) => {
+            let mut response = response::text(
+                HttpStatus::StatusUnsupportedMediaType,
+                format!("Unsupported content coding: {}", coding),
+            );
+            let supported = Encoding::get_supported()
+                .iter()
+                .map(|encoding| encoding.to_str().to_string())
+                .collect::<Vec<String>>()
+                .join(", ");
+            response.set_header(Headers::AcceptEncoding.to_str(), &supported);
+            Err(response)
+        }
+        Err(DecompressError::TooLarge) => Err(response::text(
+            HttpStatus::StatusContentTooLarge,
+            format!("Decompressed body exceeds {} bytes", limit),
+        )),
+        Err(DecompressError::Invalid(e)) => Err(response::text(
+            HttpStatus::StatusBadRequest,
+            format!("Invalid compressed body: {}", e),
+        )),
+    }
+}
+
+// Applies the path normalization policy to the request, returning a redirect
+// response when the client should retry on the canonical path.
+fn normalize_path(request: &mut request::Request, policy: &PathNormalization) -> Option<Response> {
+    let canonical_path = url::normalize(request.qualified_path());
+
+    if canonical_path == request.qualified_path() {
+        return None;
+    }
+
+    debug!(
+        "Non canonical path {} (canonical {})",
+        request.qualified_path(),
+        canonical_path
+    );
+
+    match policy {
+        PathNormalization::Strict => None,
+        PathNormalization::Lenient => {
+            request.set_qualified_path(canonical_path);
+            None
+        }
+        PathNormalization::Redirect(status) => {
+            let location = match request.path().split_once(QUERY_PARAM_START) {
+                Some((_, query)) => format!("{}{}{}", canonical_path, QUERY_PARAM_START, query),
+                None => canonical_path,
+            };
+            Some(response::redirect(status.clone(), &location))
+        }
+    }
+}
+
+fn dispatch(mut request: request::Request, routes: &RouteTable) -> Response {
+    let qualified_path = request.qualified_path().to_string();
     let route = routes.find(&qualified_path, request.method());
-    let enc = request.encoding();
 
     debug!("Route matched\n{:?}", route);
 
-    let mut response = match route {
+    match route {
         Some(route) => {
-            let path_params_list: Vec<&str> = if qualified_path == route.base_path {
-                vec![]
-            } else {
-                qualified_path
-                    .strip_prefix(&route.base_path)
-                    .unwrap_or("")
-                    .split('/')
-                    .filter(|s| !s.is_empty())
-                    .collect()
-            };
-
+            let req_segments = qualified_path.split(PATH_SEPARATOR).collect::<Vec<&str>>();
             let mut path_params = HashMap::new();
 
-            if let Some(route_params) = &route.path_params {
-                for (index, param) in path_params_list.iter().enumerate() {
-                    if let Some(param_name) = route_params.get(index) {
-                        path_params.insert(param_name.clone(), param.to_string());
-                    }
+            for (index, segment) in route.segments.iter().enumerate() {
+                if let Some(param) = param_name(segment) {
+                    let value = if is_wildcard(segment) {
+                        req_segments
+                            .get(index..)
+                            .unwrap_or_default()
+                            .join(PATH_SEPARATOR)
+                    } else {
+                        req_segments[index].to_string()
+                    };
+                    path_params.insert(param, value);
                 }
             }
 
             request.set_path_params(path_params);
 
-            (route.handler)(request)
+            let mut response = (route.handler)(request);
+            if !route.compress {
+                response.set_compressible(false);
+            }
+            response
         }
 
         //Path matched but no matches for tokens or method
         None => not_found(),
-    };
-
-    response.set_encoding(&enc);
-
-    stream.write(&response.build())?;
-    info!("{} {} {}", method, path, response.status());
-    stream.flush()?;
-
-    Ok(())
+    }
 }
 
 fn error_html() -> String {
-    return fs::read_to_string("public/404.html").unwrap();
+    // A missing error page must not take the worker down with it
+    fs::read_to_string("public/404.html")
+        .unwrap_or_else(|_| HttpStatus::StatusNotFound.to_str().to_string())
 }
 
-fn not_found() -> Response {
+pub(crate) fn not_found() -> Response {
     let content = error_html();
     response::new(HttpStatus::StatusNotFound, content, HttpContentType::HTML)
 }
+
+#[cfg(test)]
+#[path = "./router_test.rs"]
+mod test;
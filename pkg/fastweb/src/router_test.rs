@@ -0,0 +1,542 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{Shutdown, TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::compression::compress;
+    use crate::http::{Encoding, HttpStatus};
+    use crate::request::Request;
+    use crate::router::{LoadShedding, PathNormalization, RouterBuilder};
+    use crate::server::Connections;
+    use crate::{handler, request, response};
+
+    use super::super::{check_head, decode_body, dispatch, handle, normalize_path, shed};
+
+    fn router() -> RouterBuilder {
+        let mut router = crate::new();
+        router.add_route(
+            "/users/{id}/posts",
+            crate::http::HttpMethod::GET,
+            handler!(|_r: Request| response::text(HttpStatus::StatusOK, String::new())),
+            Some("user_posts"),
+        );
+        router
+    }
+
+    // Accepts a single connection on loopback and runs the connection handler
+    // on it.
+    fn serve_once(router: &RouterBuilder) -> (TcpStream, thread::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let routes = router.get_routes();
+        let configuration = router.configuration.clone();
+
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let _ = handle(stream, &routes, &configuration, &Connections::default());
+        });
+
+        (TcpStream::connect(address).unwrap(), server)
+    }
+
+    // Runs one raw request through the connection handler and returns the raw
+    // response.
+    fn roundtrip(router: &RouterBuilder, raw: &[u8]) -> String {
+        let (mut client, server) = serve_once(router);
+        client.write_all(raw).unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+
+        let mut response = Vec::new();
+        let _ = client.read_to_end(&mut response);
+        server.join().unwrap();
+        String::from_utf8_lossy(&response).to_string()
+    }
+
+    #[test]
+    fn test_url_for() {
+        let routes = router().get_routes();
+        let url = routes.url_for("user_posts", &[("id", "42")]).unwrap();
+        assert_eq!(url, "/users/42/posts");
+    }
+
+    #[test]
+    fn test_url_for_encodes_params() {
+        let routes = router().get_routes();
+        let url = routes.url_for("user_posts", &[("id", "a b/c")]).unwrap();
+        assert_eq!(url, "/users/a%20b%2Fc/posts");
+    }
+
+    #[test]
+    fn test_url_for_missing_param() {
+        let routes = router().get_routes();
+        assert!(routes.url_for("user_posts", &[]).is_err());
+    }
+
+    #[test]
+    fn test_url_for_unknown_name() {
+        let routes = router().get_routes();
+        assert!(routes.url_for("missing", &[("id", "42")]).is_err());
+    }
+
+    #[test]
+    fn test_normalize_path_lenient() {
+        let mut request = request::parse("GET /a//./b/ HTTP/1.1\r\n\r\n");
+        assert!(normalize_path(&mut request, &PathNormalization::Lenient).is_none());
+        assert_eq!(request.qualified_path(), "/a/b");
+    }
+
+    #[test]
+    fn test_normalize_path_redirect() {
+        let mut request = request::parse("GET /a//b/?x=1 HTTP/1.1\r\n\r\n");
+        let policy = PathNormalization::Redirect(HttpStatus::StatusPermanentRedirect);
+        let response = normalize_path(&mut request, &policy).unwrap();
+        assert_eq!(response.status().to_code(), 308);
+        assert_eq!(response.header("Location"), Some("/a/b?x=1"));
+    }
+
+    #[test]
+    fn test_normalize_path_strict() {
+        let mut request = request::parse("GET /a//b HTTP/1.1\r\n\r\n");
+        assert!(normalize_path(&mut request, &PathNormalization::Strict).is_none());
+        assert_eq!(request.qualified_path(), "/a//b");
+    }
+
+    #[test]
+    fn test_wildcard_path_params() {
+        let mut router = crate::new();
+        router.get(
+            "/files/{*path}",
+            handler!(|r: Request| {
+                let path = r.path_params().get("path").unwrap().to_string();
+                response::text(HttpStatus::StatusOK, path)
+            }),
+        );
+
+        let request = request::parse("GET /files/a/b.txt HTTP/1.1\r\n\r\n");
+        let response = dispatch(request, &router.get_routes());
+        assert_eq!(response.content.as_bytes(), Some(&b"a/b.txt"[..]));
+    }
+
+    fn compressed_request(coding: &str, body: &[u8]) -> request::Request {
+        let mut raw = format!(
+            "POST /upload HTTP/1.1\r\nContent-Encoding: {}\r\nContent-Length: {}\r\n\r\n",
+            coding,
+            body.len()
+        )
+        .into_bytes();
+        raw.extend(body);
+        request::parse_bytes(&raw)
+    }
+
+    #[test]
+    fn test_decode_body() {
+        let body = compress(&Encoding::GZIP, 6, b"{\"name\": \"fastweb\"}").unwrap();
+        let mut request = compressed_request("gzip", &body);
+        assert!(decode_body(&mut request, 1024).is_ok());
+        assert_eq!(request.body(), "{\"name\": \"fastweb\"}");
+        assert!(request.header("Content-Encoding").is_none());
+        assert_eq!(request.header("Content-Length"), Some("19"));
+    }
+
+    #[test]
+    fn test_decode_body_limit() {
+        let body = compress(&Encoding::DEFLATE, 6, &[b'a'; 4096]).unwrap();
+        let mut request = compressed_request("deflate", &body);
+        let response = decode_body(&mut request, 1024).unwrap_err();
+        assert_eq!(response.status().to_code(), 413);
+    }
+
+    #[test]
+    fn test_decode_body_unsupported() {
+        let mut request = compressed_request("br", b"...");
+        let response = decode_body(&mut request, 1024).unwrap_err();
+        assert_eq!(response.status().to_code(), 415);
+        assert_eq!(response.header("Accept-Encoding"), Some("gzip, deflate"));
+    }
+
+    #[test]
+    fn test_check_head() {
+        let mut router = crate::new();
+        router
+            .max_uri_length(10)
+            .max_header_count(2)
+            .max_header_bytes(64);
+        let check = |head: &str| {
+            check_head(head.as_bytes(), &router.configuration)
+                .map(|response| response.status().to_code())
+        };
+
+        assert_eq!(check("GET /short HTTP/1.1\r\nHost: a\r\n"), None);
+        assert_eq!(check("GET /much-too-long HTTP/1.1\r\n"), Some(414));
+        // Rejected before the request line is even complete
+        assert_eq!(check(&format!("GET /{}", "a".repeat(64))), Some(414));
+        assert_eq!(
+            check("GET / HTTP/1.1\r\nA: 1\r\nB: 2\r\nC: 3\r\n"),
+            Some(431)
+        );
+        assert_eq!(
+            check(&format!("GET / HTTP/1.1\r\nCookie: {}", "a".repeat(64))),
+            Some(431)
+        );
+    }
+
+    #[test]
+    fn test_body_limits() {
+        let mut router = crate::new();
+        let echo =
+            handler!(|r: Request| response::text(HttpStatus::StatusOK, r.body().to_string()));
+        router.post("/small", echo.clone());
+        router.post("/upload", echo);
+        router.max_body_size(8).route_max_body_size("/upload", 64);
+
+        let post = |path: &str, body: &str| {
+            let raw = format!(
+                "POST {} HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+                path,
+                body.len(),
+                body
+            );
+            roundtrip(&router, raw.as_bytes())
+        };
+
+        assert!(post("/small", "12345678").starts_with("HTTP/1.1 200 OK"));
+        assert!(post("/small", "123456789").starts_with("HTTP/1.1 413 Content Too Large"));
+        assert!(post("/upload", &"a".repeat(64)).ends_with(&"a".repeat(64)));
+    }
+
+    #[test]
+    fn test_route_options_before_route() {
+        let mut router = crate::new();
+        router.max_body_size(8).route_max_body_size("/upload", 64);
+        router.disable_compression("/upload");
+        router.post(
+            "/upload",
+            handler!(|r: Request| response::text(HttpStatus::StatusOK, r.body().repeat(64))),
+        );
+
+        let body = "a".repeat(64);
+        let raw = format!(
+            "POST /upload HTTP/1.1\r\nAccept-Encoding: gzip\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let response = roundtrip(&router, raw.as_bytes());
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(!response.contains("Content-Encoding"));
+    }
+
+    #[test]
+    fn test_route_options_without_route() {
+        let mut router = crate::new();
+        router.disable_compression("/export");
+        router.route_max_body_size("/export", 64);
+        assert!(router.get_routes().0.is_empty());
+    }
+
+    #[test]
+    fn test_disable_compression_for_prefix() {
+        let mut router = crate::new();
+        router.get(
+            "/export/{*path}",
+            handler!(|_| response::text(HttpStatus::StatusOK, "a".repeat(4096))),
+        );
+        router.disable_compression("/export");
+
+        let response = roundtrip(
+            &router,
+            b"GET /export/data.txt HTTP/1.1\r\nAccept-Encoding: gzip\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(!response.contains("Content-Encoding"));
+        assert!(response.ends_with(&"a".repeat(4096)));
+    }
+
+    #[test]
+    fn test_reads_body_across_packets() {
+        let mut router = crate::new();
+        router.buffer_size(16);
+        router.post(
+            "/echo",
+            handler!(|r: Request| response::text(HttpStatus::StatusOK, r.body().to_string())),
+        );
+
+        let body = "x".repeat(5000);
+        let raw = format!(
+            "POST /echo HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        assert!(roundtrip(&router, raw.as_bytes()).ends_with(&body));
+    }
+
+    fn upload_router() -> RouterBuilder {
+        let mut router = crate::new();
+        router.post(
+            "/upload",
+            handler!(|r: Request| response::text(HttpStatus::StatusOK, r.body().to_string())),
+        );
+        router.max_body_size(1024);
+        router
+    }
+
+    #[test]
+    fn test_expect_continue() {
+        let (mut client, server) = serve_once(&upload_router());
+        client
+            .write_all(
+                b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\n",
+            )
+            .unwrap();
+
+        let mut interim = [0; 25];
+        client.read_exact(&mut interim).unwrap();
+        assert_eq!(&interim, b"HTTP/1.1 100 Continue\r\n\r\n");
+
+        client.write_all(b"hello").unwrap();
+        client.shutdown(Shutdown::Write).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        server.join().unwrap();
+
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("hello"));
+    }
+
+    #[test]
+    fn test_expect_rejected_before_body() {
+        let router = upload_router();
+        let send = |head: &str| roundtrip(&router, head.as_bytes());
+
+        let response =
+            send("POST /missing HTTP/1.1\r\nContent-Length: 5\r\nExpect: 100-continue\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+
+        let response =
+            send("POST /upload HTTP/1.1\r\nContent-Length: 4096\r\nExpect: 100-continue\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 413 Content Too Large"));
+
+        let response = send("POST /upload HTTP/1.1\r\nContent-Length: 5\r\nExpect: teapot\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 417 Expectation Failed"));
+    }
+
+    #[test]
+    fn test_connection_closed_after_response() {
+        let mut router = upload_router();
+        router.get(
+            "/upgrade",
+            handler!(|_r: Request| {
+                let mut response = response::text(HttpStatus::StatusOK, String::new());
+                response.set_header("Connection", "upgrade");
+                response
+            }),
+        );
+
+        // Only the first of two requests sent together is answered
+        let response = roundtrip(
+            &router,
+            b"POST /upload HTTP/1.1\r\nContent-Length: 3\r\n\r\nonePOST /upload HTTP/1.1\r\nContent-Length: 3\r\n\r\ntwo",
+        );
+        assert_eq!(response.matches("HTTP/1.1").count(), 1);
+        assert!(response.contains("Connection: close\r\n"));
+        assert!(response.ends_with("one"));
+
+        let response = roundtrip(&router, b"GET /upgrade HTTP/1.1\r\n\r\n");
+        assert!(response.contains("Connection: upgrade\r\n"));
+        assert!(!response.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_http10() {
+        let mut router = upload_router();
+        router.get(
+            "/stream",
+            handler!(|_r: Request| response::stream(
+                HttpStatus::StatusOK,
+                vec![b"a".to_vec(), b"b".to_vec()].into_iter(),
+                "text/plain".into()
+            )),
+        );
+
+        let response = roundtrip(&router, b"GET /stream HTTP/1.0\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.0 200 OK"));
+        assert!(response.contains("Connection: close\r\n"));
+        assert!(!response.contains("Transfer-Encoding"));
+        assert!(response.ends_with("\r\n\r\nab"));
+
+        let response = roundtrip(
+            &router,
+            b"POST /upload HTTP/1.0\r\nConnection: keep-alive\r\nContent-Length: 2\r\n\r\nhi",
+        );
+        assert!(response.starts_with("HTTP/1.0 200 OK"));
+        assert!(response.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let response = roundtrip(&upload_router(), b"GET / HTTP/2.0\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 505 HTTP Version Not Supported"));
+        assert!(response.contains("Connection: close\r\n"));
+    }
+
+    // Sends a request without closing the write side and reads until the
+    // server closes the connection.
+    fn stall(router: &RouterBuilder, raw: &[u8]) -> String {
+        let (mut client, server) = serve_once(router);
+        client.write_all(raw).unwrap();
+
+        let mut response = String::new();
+        let _ = client.read_to_string(&mut response);
+        server.join().unwrap();
+        response
+    }
+
+    #[test]
+    fn test_read_timeouts() {
+        let mut router = upload_router();
+        router
+            .header_read_timeout(Duration::from_millis(100))
+            .body_read_timeout(Duration::from_millis(100));
+
+        let response = stall(&router, b"POST /upload HTTP/1.1\r\nContent-");
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout"));
+        assert!(response.contains("Connection: close\r\n"));
+
+        let response = stall(
+            &router,
+            b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\n\r\nhe",
+        );
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout"));
+    }
+
+    #[test]
+    fn test_header_timeout_not_extended_by_trickling() {
+        let mut router = upload_router();
+        router.header_read_timeout(Duration::from_millis(200));
+
+        let (mut client, server) = serve_once(&router);
+        let started = Instant::now();
+        for byte in b"GET /upload HTTP/1.1\r\nX: ".iter().cycle() {
+            if client.write_all(&[*byte]).is_err() || started.elapsed() > Duration::from_secs(2) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+
+        let mut response = String::new();
+        let _ = client.read_to_string(&mut response);
+        server.join().unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(response.starts_with("HTTP/1.1 408 Request Timeout"));
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        let mut router = upload_router();
+        router.idle_timeout(Duration::from_millis(100));
+
+        // Closed without a response when the request never starts
+        let started = Instant::now();
+        let response = stall(&router, b"");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert_eq!(response, "");
+    }
+
+    #[test]
+    fn test_shed() {
+        let mut router = crate::new();
+        let stats = router.stats();
+        let shed_once = |router: &RouterBuilder| {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+            let (stream, _) = listener.accept().unwrap();
+            shed(stream, &router.configuration);
+
+            let mut response = String::new();
+            let _ = client.read_to_string(&mut response);
+            response
+        };
+
+        let response = shed_once(&router);
+        assert!(response.starts_with("HTTP/1.1 503 Service Unavailable"));
+        assert!(response.contains("Retry-After: 1\r\n"));
+
+        router.load_shedding(LoadShedding::Close);
+        assert_eq!(shed_once(&router), "");
+        assert_eq!(stats.shed(), 2);
+    }
+
+    #[test]
+    fn test_transfer_encoding_rejected() {
+        let router = upload_router();
+
+        // The chunks must not be taken for another request
+        let response = roundtrip(
+            &router,
+            b"POST /upload HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\nGET /upload HTTP/1.1\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 501 Not Implemented"));
+        assert!(response.contains("Connection: close\r\n"));
+        assert_eq!(response.matches("HTTP/1.1").count(), 1);
+
+        let response = roundtrip(
+            &router,
+            b"POST /upload HTTP/1.1\r\nContent-Length: 5\r\nTransfer-Encoding: chunked\r\n\r\nhello",
+        );
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+        assert!(response.contains("Connection: close\r\n"));
+    }
+
+    #[test]
+    fn test_malformed_request_line() {
+        let router = upload_router();
+
+        let response = roundtrip(&router, b"OPTIONS / HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 501 Not Implemented"));
+
+        let response = roundtrip(&router, b"GET\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+    }
+
+    #[test]
+    fn test_empty_lines_before_request() {
+        let response = roundtrip(
+            &upload_router(),
+            b"\r\n\r\nPOST /upload HTTP/1.1\r\nContent-Length: 2\r\n\r\nho",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.ends_with("ho"));
+    }
+
+    #[test]
+    fn test_server_header() {
+        let mut router = crate::new();
+        router.get(
+            "/default",
+            handler!(|_| response::text(HttpStatus::StatusOK, String::new())),
+        );
+        router.get(
+            "/custom",
+            handler!(|_| {
+                let mut response = response::text(HttpStatus::StatusOK, String::new());
+                response.set_header("Server", "custom");
+                response
+            }),
+        );
+
+        let response = roundtrip(
+            &router,
+            b"GET /default HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.contains("\r\nServer: fastweb\r\n"));
+
+        let response = roundtrip(
+            &router,
+            b"GET /custom HTTP/1.1\r\nConnection: close\r\n\r\n",
+        );
+        assert!(response.contains("\r\nServer: custom\r\n"));
+        assert_eq!(response.matches("Server: ").count(), 1);
+    }
+}
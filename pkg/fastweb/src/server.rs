@@ -0,0 +1,315 @@
+use std::{
+    collections::HashMap,
+    io,
+    net::{Ipv4Addr, Ipv6Addr, Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use logger::{info, warn};
+use workers::ThreadPool;
+
+use crate::{
+    router::{self, RouteTable},
+    stats::ServerStats,
+    Configuration,
+};
+
+// How often a stopping server checks whether in-flight requests finished.
+static DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// How often the signal watcher checks for SIGINT and SIGTERM.
+static SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+// Set from the signal handler, which may do nothing but store to an atomic,
+// and cleared by the watcher taking it, so each signal stops one server.
+static SIGNALLED: AtomicBool = AtomicBool::new(false);
+
+/// The open connections of a server, so a shutdown can close the idle ones
+/// and wait for the busy ones.
+#[derive(Default)]
+pub(crate) struct Connections {
+    stopping: AtomicBool,
+    forced: AtomicBool,
+    next_id: AtomicU64,
+    // The stream of every connection, and whether it waits for a request
+    open: Mutex<HashMap<u64, (TcpStream, bool)>>,
+    drain_deadline: Mutex<Option<Instant>>,
+}
+
+impl Connections {
+    pub(crate) fn register(&self, stream: &TcpStream) -> io::Result<Tracked<'_>> {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.open
+            .lock()
+            .unwrap()
+            .insert(id, (stream.try_clone()?, false));
+        Ok(Tracked {
+            connections: self,
+            id,
+        })
+    }
+
+    /// Whether the server stopped accepting, connections close after the
+    /// request they are serving.
+    pub(crate) fn is_stopping(&self) -> bool {
+        self.stopping.load(Ordering::SeqCst)
+    }
+
+    /// Whether the drain timeout passed, connections not started yet are
+    /// closed without being served.
+    pub(crate) fn is_forced(&self) -> bool {
+        self.forced.load(Ordering::SeqCst)
+    }
+
+    // Stops the server, leaving in-flight requests until the deadline, if any.
+    // Returns false if it was already stopping.
+    fn stop(&self, drain_timeout: Option<Duration>) -> bool {
+        *self.drain_deadline.lock().unwrap() =
+            drain_timeout.map(|timeout| Instant::now() + timeout);
+
+        if self.stopping.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+
+        let open = self.open.lock().unwrap();
+        info!("Shutting down, {} connections open", open.len());
+
+        for (stream, _) in open.values().filter(|(_, idle)| *idle) {
+            let _ = stream.shutdown(Shutdown::Read);
+        }
+
+        true
+    }
+
+    // Closes every connection, in-flight requests included.
+    fn force(&self) {
+        self.forced.store(true, Ordering::SeqCst);
+
+        let open = self.open.lock().unwrap();
+        warn!("Drain timeout passed, closing {} connections", open.len());
+
+        for (stream, _) in open.values() {
+            let _ = stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// Waits for the connections handed to the pool to be served, closing them
+/// once the drain deadline passed, then joins the workers.
+pub(crate) fn drain(pool: ThreadPool, connections: &Connections) {
+    while pool.pending() > 0 {
+        let deadline = *connections.drain_deadline.lock().unwrap();
+        if deadline.is_some_and(|deadline| deadline <= Instant::now()) {
+            connections.force();
+            break;
+        }
+        thread::sleep(DRAIN_POLL_INTERVAL);
+    }
+
+    drop(pool);
+    info!("Server stopped");
+}
+
+/// A registered connection, removed from the registry when dropped.
+pub(crate) struct Tracked<'a> {
+    connections: &'a Connections,
+    id: u64,
+}
+
+impl Tracked<'_> {
+    /// Marks the connection as waiting for its next request or serving one.
+    /// Returns false if it should close instead of waiting.
+    pub(crate) fn set_idle(&self, idle: bool) -> bool {
+        if let Some((_, state)) = self.connections.open.lock().unwrap().get_mut(&self.id) {
+            *state = idle;
+        }
+
+        // Checked after marking, so either this or the shutdown sees the other
+        !(idle && self.connections.is_stopping())
+    }
+}
+
+impl Drop for Tracked<'_> {
+    fn drop(&mut self) {
+        self.connections.open.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// A server bound to its address, see [`RouterBuilder::bind`](crate::router::RouterBuilder::bind).
+pub struct Server {
+    listener: TcpListener,
+    local_addr: SocketAddr,
+    routes: Arc<RouteTable>,
+    configuration: Arc<Configuration>,
+    connections: Arc<Connections>,
+}
+
+impl Server {
+    pub(crate) fn new(
+        listener: TcpListener,
+        routes: RouteTable,
+        configuration: Configuration,
+    ) -> io::Result<Server> {
+        Ok(Server {
+            local_addr: listener.local_addr()?,
+            listener,
+            routes: Arc::new(routes),
+            configuration: Arc::new(configuration),
+            connections: Arc::new(Connections::default()),
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Serves on a background thread, returning a handle to stop the server.
+    pub fn spawn(self) -> ServerHandle {
+        let local_addr = self.local_addr;
+        let connections = Arc::clone(&self.connections);
+        let stats = Arc::clone(&self.configuration.stats);
+        let acceptor = thread::spawn(move || self.run());
+
+        ServerHandle {
+            local_addr,
+            connections,
+            stats,
+            acceptor: Some(acceptor),
+        }
+    }
+
+    /// Serves on the current thread until the handle of a spawned server
+    /// stops it or, if enabled, until SIGINT or SIGTERM.
+    pub fn run(self) {
+        info!("Starting server on {}", self.local_addr);
+
+        if self.configuration.handle_signals {
+            stop_on_signal(
+                Arc::clone(&self.connections),
+                self.local_addr,
+                self.configuration.drain_timeout,
+            );
+        }
+
+        router::accept(
+            self.listener,
+            self.routes,
+            self.configuration,
+            self.connections,
+        );
+    }
+}
+
+/// A running server. Dropping the handle leaves the server running until
+/// the process exits or, if enabled, receives SIGINT or SIGTERM.
+pub struct ServerHandle {
+    local_addr: SocketAddr,
+    connections: Arc<Connections>,
+    stats: Arc<ServerStats>,
+    acceptor: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn stats(&self) -> Arc<ServerStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// Stops accepting connections, closes connections still waiting for
+    /// their request and waits for in-flight requests to finish before
+    /// joining the workers.
+    pub fn shutdown(mut self) {
+        stop(&self.connections, self.local_addr, None);
+        self.join();
+    }
+
+    /// Like [`ServerHandle::shutdown`], but closes connections still serving
+    /// a request once `timeout` passed. Handlers cannot be interrupted, the
+    /// workers are joined once the running ones return.
+    pub fn shutdown_timeout(mut self, timeout: Duration) {
+        stop(&self.connections, self.local_addr, Some(timeout));
+        self.join();
+    }
+
+    /// Blocks until the server is shut down by SIGINT or SIGTERM, if enabled.
+    pub fn wait(mut self) {
+        self.join();
+    }
+
+    fn join(&mut self) {
+        if let Some(acceptor) = self.acceptor.take() {
+            let _ = acceptor.join();
+        }
+    }
+}
+
+// Stops the server and wakes the acceptor blocked on the listener.
+fn stop(connections: &Connections, local_addr: SocketAddr, drain_timeout: Option<Duration>) {
+    if connections.stop(drain_timeout) {
+        let _ = TcpStream::connect(wake_address(local_addr));
+    }
+}
+
+// A listener on all interfaces is reached through loopback.
+fn wake_address(mut local_addr: SocketAddr) -> SocketAddr {
+    if local_addr.ip().is_unspecified() {
+        match local_addr {
+            SocketAddr::V4(_) => local_addr.set_ip(Ipv4Addr::LOCALHOST.into()),
+            SocketAddr::V6(_) => local_addr.set_ip(Ipv6Addr::LOCALHOST.into()),
+        }
+    }
+    local_addr
+}
+
+// Shuts the server down gracefully on SIGINT or SIGTERM, giving in-flight
+// requests `drain_timeout` to finish.
+fn stop_on_signal(connections: Arc<Connections>, local_addr: SocketAddr, drain_timeout: Duration) {
+    // A signal received before this server started is not meant for it
+    SIGNALLED.store(false, Ordering::SeqCst);
+    install_signal_handlers();
+
+    thread::spawn(move || {
+        while !connections.is_stopping() {
+            if SIGNALLED.swap(false, Ordering::SeqCst) {
+                info!("Received shutdown signal");
+                stop(&connections, local_addr, Some(drain_timeout));
+                break;
+            }
+            thread::sleep(SIGNAL_POLL_INTERVAL);
+        }
+    });
+}
+
+#[cfg(unix)]
+fn install_signal_handlers() {
+    extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+
+    const SIGINT: i32 = 2;
+    const SIGTERM: i32 = 15;
+
+    extern "C" fn on_signal(_: i32) {
+        SIGNALLED.store(true, Ordering::SeqCst);
+    }
+
+    unsafe {
+        signal(SIGINT, on_signal);
+        signal(SIGTERM, on_signal);
+    }
+}
+
+#[cfg(not(unix))]
+fn install_signal_handlers() {}
+
+#[cfg(test)]
+#[path = "./server_test.rs"]
+mod test;
@@ -0,0 +1,160 @@
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    use crate::handler;
+    use crate::http::HttpStatus;
+    use crate::request::Request;
+    use crate::response;
+    use crate::router::RouterBuilder;
+    use crate::server::ServerHandle;
+
+    fn router(delay: Duration) -> RouterBuilder {
+        let mut router = crate::new();
+        router.get(
+            "/slow",
+            handler!(move |_r: Request| {
+                thread::sleep(delay);
+                response::text(HttpStatus::StatusOK, "done".to_string())
+            }),
+        );
+        router
+            .host("127.0.0.1".to_string())
+            .port(0)
+            .handle_signals(false);
+        router
+    }
+
+    // Serves a route taking `delay` to answer on an ephemeral port.
+    fn start(delay: Duration) -> ServerHandle {
+        router(delay).serve().unwrap()
+    }
+
+    fn request(server: &ServerHandle) -> TcpStream {
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client
+            .write_all(b"GET /slow HTTP/1.1\r\nHost: test\r\n\r\n")
+            .unwrap();
+        client
+    }
+
+    #[test]
+    fn test_shutdown_finishes_in_flight_requests() {
+        let server = start(Duration::from_millis(200));
+        let address = server.local_addr();
+        let mut client = request(&server);
+        thread::sleep(Duration::from_millis(50));
+
+        server.shutdown();
+
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        assert!(response.contains("Connection: close\r\n"));
+        assert!(response.ends_with("done"));
+        assert!(TcpStream::connect(address).is_err());
+    }
+
+    #[test]
+    fn test_connection_closed_after_response() {
+        let server = start(Duration::ZERO);
+        let mut client = request(&server);
+
+        let mut response = Vec::new();
+        let mut buffer = [0; 512];
+        while !response.ends_with(b"done") {
+            let read = client.read(&mut buffer).unwrap();
+            assert!(read > 0);
+            response.extend_from_slice(&buffer[..read]);
+        }
+
+        // The connection is closed after the response
+        assert_eq!(client.read(&mut buffer).unwrap(), 0);
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_shutdown_closes_idle_connections() {
+        let server = start(Duration::ZERO);
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        thread::sleep(Duration::from_millis(50));
+
+        // Closed without a 408 although no request was sent
+        let started = Instant::now();
+        server.shutdown();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert!(response.is_empty());
+    }
+
+    #[test]
+    fn test_shutdown_timeout() {
+        let server = start(Duration::from_millis(500));
+        let mut client = request(&server);
+        thread::sleep(Duration::from_millis(50));
+
+        let started = Instant::now();
+        let shutdown = thread::spawn(move || server.shutdown_timeout(Duration::from_millis(50)));
+
+        // Closed before the handler could answer
+        let mut response = String::new();
+        let _ = client.read_to_string(&mut response);
+        assert!(started.elapsed() < Duration::from_millis(400));
+        assert!(response.is_empty());
+        shutdown.join().unwrap();
+    }
+
+    #[test]
+    fn test_bind_ephemeral_port() {
+        let server = router(Duration::ZERO).bind().unwrap();
+        let address = server.local_addr();
+        assert_ne!(address.port(), 0);
+
+        let handle = server.spawn();
+        assert_eq!(handle.local_addr(), address);
+
+        let mut client = request(&handle);
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.ends_with("done"));
+
+        let stats = handle.stats();
+        handle.shutdown();
+        assert_eq!(stats.accepted(), 1);
+    }
+
+    #[test]
+    fn test_shutdown_after_handler_panic() {
+        let mut router = router(Duration::ZERO);
+        router.workers(1);
+        router.get(
+            "/panic",
+            handler!(|_r: Request| -> response::Response { panic!("handler failed") }),
+        );
+        let server = router.serve().unwrap();
+
+        let mut client = TcpStream::connect(server.local_addr()).unwrap();
+        client
+            .write_all(b"GET /panic HTTP/1.1\r\nHost: test\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        let _ = client.read_to_string(&mut response);
+        assert!(response.is_empty());
+
+        // The only worker survived and serves the next connection
+        let mut client = request(&server);
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        client.read_to_string(&mut response).unwrap();
+        assert!(response.ends_with("done"));
+
+        let started = Instant::now();
+        server.shutdown();
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,350 @@
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use logger::{debug, error};
+
+use crate::{
+    cookie::{Cookie, SameSite},
+    crypto,
+    request::Request,
+    response::Response,
+};
+
+pub type SessionData = HashMap<String, String>;
+
+// Random bytes in a session ID, hex encoded in the cookie.
+static ID_LENGTH: usize = 32;
+// Random bytes encrypting each cookie differently, even for the same ID.
+static NONCE_LENGTH: usize = 16;
+static SIGNATURE_SEPARATOR: char = '.';
+
+/// Persists session data between requests.
+pub trait SessionStore: Send + Sync {
+    /// Returns the data of the session, `None` if it is unknown or expired.
+    fn load(&self, id: &str) -> Option<SessionData>;
+
+    /// Stores the data of the session, expiring it after `ttl` without access.
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration);
+
+    fn destroy(&self, id: &str);
+}
+
+// How often expired sessions nobody asked for again are swept.
+static SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+struct Entries {
+    sessions: HashMap<String, (Instant, SessionData)>,
+    last_sweep: Option<Instant>,
+}
+
+/// Keeps sessions in memory, they are lost when the server restarts.
+pub struct MemoryStore {
+    entries: Mutex<Entries>,
+    sweep_interval: Duration,
+}
+
+impl Default for MemoryStore {
+    fn default() -> MemoryStore {
+        MemoryStore {
+            entries: Mutex::new(Entries::default()),
+            sweep_interval: SWEEP_INTERVAL,
+        }
+    }
+}
+
+impl MemoryStore {
+    pub fn new() -> MemoryStore {
+        MemoryStore::default()
+    }
+
+    /// How often expired sessions are removed, every minute by default.
+    /// Expired sessions are never loaded, the sweep only frees their memory.
+    pub fn sweep_interval(mut self, sweep_interval: Duration) -> MemoryStore {
+        self.sweep_interval = sweep_interval;
+        self
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> Option<SessionData> {
+        let sessions = &mut self.entries.lock().unwrap().sessions;
+
+        match sessions.get(id) {
+            Some((expires, _)) if *expires <= Instant::now() => {
+                sessions.remove(id);
+                None
+            }
+            Some((_, data)) => Some(data.clone()),
+            None => None,
+        }
+    }
+
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+
+        // Sweep expired sessions so abandoned ones do not pile up, at most once
+        // per interval so that a request does not pay for all of them
+        let due = entries
+            .last_sweep
+            .is_none_or(|last_sweep| now.duration_since(last_sweep) >= self.sweep_interval);
+        if due {
+            entries.sessions.retain(|_, (expires, _)| *expires > now);
+            entries.last_sweep = Some(now);
+        }
+
+        entries
+            .sessions
+            .insert(id.to_string(), (now + ttl, data.clone()));
+    }
+
+    fn destroy(&self, id: &str) {
+        self.entries.lock().unwrap().sessions.remove(id);
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    data: SessionData,
+    modified: bool,
+    destroyed: bool,
+    regenerate: bool,
+}
+
+/// The session of a request. Clones share the same data, so changes made by
+/// a handler are saved once it returns.
+#[derive(Clone)]
+pub struct Session {
+    id: String,
+    is_new: bool,
+    state: Arc<Mutex<State>>,
+}
+
+impl Session {
+    fn new(id: String, is_new: bool, data: SessionData) -> Session {
+        Session {
+            id,
+            is_new,
+            state: Arc::new(Mutex::new(State {
+                data,
+                ..State::default()
+            })),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Whether the session was started by this request.
+    pub fn is_new(&self) -> bool {
+        self.is_new
+    }
+
+    pub fn get(&self, key: &str) -> Option<String> {
+        return self.state.lock().unwrap().data.get(key).cloned();
+    }
+
+    pub fn set(&self, key: &str, value: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.data.insert(key.to_string(), value.to_string());
+        state.modified = true;
+    }
+
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut state = self.state.lock().unwrap();
+        state.modified = true;
+        state.data.remove(key)
+    }
+
+    /// Moves the data to a new session ID, e.g. after logging in, so an ID
+    /// known before authentication cannot be reused.
+    pub fn regenerate(&self) {
+        self.state.lock().unwrap().regenerate = true;
+    }
+
+    /// Deletes the session and its cookie, e.g. when logging out.
+    pub fn destroy(&self) {
+        self.state.lock().unwrap().destroyed = true;
+    }
+}
+
+impl Debug for Session {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        // The ID is a credential, keep it out of the logs
+        f.debug_struct("Session")
+            .field("is_new", &self.is_new)
+            .finish()
+    }
+}
+
+/// Issues an encrypted and signed session ID cookie and loads the session of
+/// every request from a [`SessionStore`].
+pub struct Sessions {
+    encryption_key: [u8; 32],
+    signing_key: [u8; 32],
+    store: Arc<dyn SessionStore>,
+    cookie_name: String,
+    ttl: Duration,
+    secure: bool,
+}
+
+impl Sessions {
+    /// Creates sessions encrypted and signed with keys derived from `secret`,
+    /// which must be at least 32 bytes and the same across restarts for
+    /// sessions to survive them.
+    pub fn new(secret: &[u8]) -> Sessions {
+        assert!(
+            secret.len() >= 32,
+            "Session secret must be at least 32 bytes"
+        );
+
+        Sessions {
+            // Separate keys, so neither use of the secret can help attack the other
+            encryption_key: crypto::hmac_sha256(secret, b"fastweb session encryption"),
+            signing_key: crypto::hmac_sha256(secret, b"fastweb session signing"),
+            store: Arc::new(MemoryStore::new()),
+            cookie_name: "fastweb.sid".to_string(),
+            ttl: Duration::from_secs(24 * 60 * 60),
+            secure: false,
+        }
+    }
+
+    pub fn store<S: SessionStore + 'static>(mut self, store: S) -> Sessions {
+        self.store = Arc::new(store);
+        self
+    }
+
+    pub fn cookie_name(mut self, cookie_name: &str) -> Sessions {
+        self.cookie_name = cookie_name.to_string();
+        self
+    }
+
+    /// Sessions expire after this long without a request.
+    pub fn ttl(mut self, ttl: Duration) -> Sessions {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Only sends the cookie over HTTPS, set when behind a TLS terminating proxy.
+    pub fn secure(mut self, secure: bool) -> Sessions {
+        self.secure = secure;
+        self
+    }
+
+    // Encrypts the session ID, then signs the nonce and ciphertext, giving
+    // `hex(nonce ciphertext).hex(signature)`.
+    fn seal(&self, id: &str) -> String {
+        let mut sealed = crypto::random_bytes(NONCE_LENGTH);
+        let mut ciphertext = id.as_bytes().to_vec();
+        crypto::apply_keystream(&self.encryption_key, &sealed, &mut ciphertext);
+        sealed.extend(ciphertext);
+
+        let signature = crypto::hmac_sha256(&self.signing_key, &sealed);
+        format!(
+            "{}{}{}",
+            crypto::to_hex(&sealed),
+            SIGNATURE_SEPARATOR,
+            crypto::to_hex(&signature)
+        )
+    }
+
+    // Returns the session ID of a cookie value if its signature is valid.
+    fn open(&self, value: &str) -> Option<String> {
+        let (sealed, signature) = value.rsplit_once(SIGNATURE_SEPARATOR)?;
+        let sealed = crypto::from_hex(sealed)?;
+        let signature = crypto::from_hex(signature)?;
+
+        // Checked before decrypting, so forged ciphertexts are never looked at
+        let expected = crypto::hmac_sha256(&self.signing_key, &sealed);
+        if sealed.len() < NONCE_LENGTH || !crypto::constant_time_eq(&expected, &signature) {
+            return None;
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LENGTH);
+        let mut id = ciphertext.to_vec();
+        crypto::apply_keystream(&self.encryption_key, nonce, &mut id);
+        String::from_utf8(id).ok()
+    }
+
+    fn cookie(&self, value: &str) -> Result<Cookie, String> {
+        Ok(Cookie::new(&self.cookie_name, value)?
+            .path("/")?
+            .http_only(true)
+            .secure(self.secure)
+            .same_site(SameSite::Lax))
+    }
+
+    // Sets the session cookie, which only fails if the cookie name is invalid.
+    fn set_cookie(&self, response: &mut Response, cookie: Result<Cookie, String>) {
+        match cookie {
+            Ok(cookie) => response.set_cookie(&cookie),
+            Err(e) => error!("Failed to set the session cookie: {}", e),
+        }
+    }
+
+    /// Attaches the session named by the request's cookie, or a new empty one
+    /// if the cookie is missing, forged or expired.
+    pub fn start(&self, request: &mut Request) -> Session {
+        let existing = request
+            .cookie(&self.cookie_name)
+            .and_then(|value| self.open(&value))
+            .and_then(|id| Some((self.store.load(&id)?, id)));
+
+        let session = match existing {
+            Some((data, id)) => Session::new(id, false, data),
+            None => Session::new(generate_id(), true, SessionData::new()),
+        };
+
+        debug!("Started {:?}", session);
+        request.set_session(session.clone());
+        session
+    }
+
+    /// Saves the session once the handler returned and sets the cookie when
+    /// the session ID changed.
+    pub fn finish(&self, session: &Session, response: &mut Response) {
+        let state = session.state.lock().unwrap();
+
+        if state.destroyed {
+            self.store.destroy(&session.id);
+            if !session.is_new {
+                let removal = Cookie::removal(&self.cookie_name).and_then(|c| c.path("/"));
+                self.set_cookie(response, removal);
+            }
+            return;
+        }
+
+        // New sessions are only stored once they hold something
+        if session.is_new && !state.modified {
+            return;
+        }
+
+        let id = if state.regenerate && !session.is_new {
+            self.store.destroy(&session.id);
+            generate_id()
+        } else {
+            session.id.clone()
+        };
+
+        // Saving existing sessions too extends their expiry on every request
+        self.store.save(&id, &state.data, self.ttl);
+
+        if session.is_new || id != session.id {
+            self.set_cookie(response, self.cookie(&self.seal(&id)));
+        }
+    }
+}
+
+fn generate_id() -> String {
+    crypto::to_hex(&crypto::random_bytes(ID_LENGTH))
+}
+
+#[cfg(test)]
+#[path = "./session_test.rs"]
+mod test;
@@ -0,0 +1,150 @@
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, thread, time::Duration};
+
+    use super::super::{MemoryStore, SessionData, SessionStore, Sessions};
+    use crate::http::HttpStatus;
+    use crate::{request, response};
+
+    static SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn request(cookie: Option<&str>) -> request::Request {
+        let header = cookie
+            .map(|cookie| format!("Cookie: {}\r\n", cookie))
+            .unwrap_or_default();
+        request::parse(&format!("GET / HTTP/1.1\r\n{}\r\n", header))
+    }
+
+    // Runs a request through the sessions, returning the Set-Cookie value.
+    fn roundtrip(
+        sessions: &Sessions,
+        cookie: Option<&str>,
+        handler: impl Fn(&request::Request),
+    ) -> Option<String> {
+        let mut request = request(cookie);
+        let session = sessions.start(&mut request);
+        handler(&request);
+
+        let mut response = response::text(HttpStatus::StatusOK, String::new());
+        sessions.finish(&session, &mut response);
+        response
+            .header("Set-Cookie")
+            .map(|value| value.split(';').next().unwrap().to_string())
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let sessions = Sessions::new(SECRET);
+
+        // Nothing stored, no cookie issued
+        assert!(roundtrip(&sessions, None, |_| ()).is_none());
+
+        let cookie = roundtrip(&sessions, None, |request| {
+            request.session().unwrap().set("user", "admin");
+        })
+        .unwrap();
+
+        roundtrip(&sessions, Some(&cookie), |request| {
+            let session = request.session().unwrap();
+            assert!(!session.is_new());
+            assert_eq!(session.get("user"), Some("admin".to_string()));
+        });
+    }
+
+    #[test]
+    fn test_forged_cookie() {
+        let sessions = Sessions::new(SECRET);
+        let cookie = roundtrip(&sessions, None, |request| {
+            request.session().unwrap().set("user", "admin");
+        })
+        .unwrap();
+
+        let other = Sessions::new(b"another secret of at least 32 bytes");
+        // Keep the signature but claim another session ID
+        let (_, signature) = cookie.rsplit_once('.').unwrap();
+        let forged = format!("fastweb.sid={}.{}", "0".repeat(64), signature);
+        for (sessions, cookie) in [(&other, &cookie), (&sessions, &forged)] {
+            roundtrip(sessions, Some(cookie), |request| {
+                assert!(request.session().unwrap().is_new());
+            });
+        }
+    }
+
+    #[test]
+    fn test_regenerate_and_destroy() {
+        let sessions = Sessions::new(SECRET);
+        let cookie = roundtrip(&sessions, None, |request| {
+            request.session().unwrap().set("user", "admin");
+        })
+        .unwrap();
+
+        let regenerated = roundtrip(&sessions, Some(&cookie), |request| {
+            request.session().unwrap().regenerate();
+        })
+        .unwrap();
+        assert_ne!(regenerated, cookie);
+
+        roundtrip(&sessions, Some(&cookie), |request| {
+            assert!(request.session().unwrap().is_new());
+        });
+
+        let removal = roundtrip(&sessions, Some(&regenerated), |request| {
+            request.session().unwrap().destroy();
+        });
+        assert_eq!(removal, Some("fastweb.sid=".to_string()));
+    }
+
+    #[test]
+    fn test_memory_store_ttl() {
+        let store = MemoryStore::new();
+        let data = SessionData::from([("user".to_string(), "admin".to_string())]);
+
+        store.save("a", &data, Duration::from_millis(10));
+        assert_eq!(store.load("a"), Some(data));
+
+        thread::sleep(Duration::from_millis(20));
+        assert!(store.load("a").is_none());
+    }
+
+    #[test]
+    fn test_memory_store_sweep() {
+        let store = MemoryStore::new().sweep_interval(Duration::from_millis(50));
+        let data = SessionData::new();
+        let count = |store: &MemoryStore| store.entries.lock().unwrap().sessions.len();
+
+        store.save("a", &data, Duration::from_millis(10));
+        thread::sleep(Duration::from_millis(20));
+
+        // Not swept before the interval passed
+        store.save("b", &data, Duration::from_secs(60));
+        assert_eq!(count(&store), 2);
+
+        thread::sleep(Duration::from_millis(50));
+        store.save("c", &data, Duration::from_secs(60));
+        assert_eq!(count(&store), 2);
+        assert!(store.load("a").is_none());
+    }
+
+    #[test]
+    fn test_cookie_is_encrypted() {
+        let sessions = Sessions::new(SECRET);
+        let first = RefCell::new(String::new());
+
+        let cookie = roundtrip(&sessions, None, |request| {
+            let session = request.session().unwrap();
+            *first.borrow_mut() = session.id().to_string();
+            session.set("user", "admin");
+        })
+        .unwrap();
+        let first = first.into_inner();
+        assert!(!cookie.contains(&first));
+
+        // The same ID is sealed differently every time
+        assert_ne!(sessions.seal(&first), sessions.seal(&first));
+        assert_eq!(sessions.open(&sessions.seal(&first)), Some(first.clone()));
+
+        roundtrip(&sessions, Some(&cookie), |request| {
+            assert_eq!(request.session().unwrap().id(), first);
+        });
+    }
+}
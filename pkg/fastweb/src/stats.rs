@@ -0,0 +1,29 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters of the connections seen by a server, shared with the router so
+/// they can be read while it serves, e.g. from a metrics endpoint.
+#[derive(Debug, Default)]
+pub struct ServerStats {
+    accepted: AtomicU64,
+    shed: AtomicU64,
+}
+
+impl ServerStats {
+    /// Connections accepted from the listener, shed ones included.
+    pub fn accepted(&self) -> u64 {
+        self.accepted.load(Ordering::Relaxed)
+    }
+
+    /// Connections turned away because too many were waiting for a worker.
+    pub fn shed(&self) -> u64 {
+        self.shed.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn record_accepted(&self) {
+        self.accepted.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_shed(&self) {
+        self.shed.fetch_add(1, Ordering::Relaxed);
+    }
+}
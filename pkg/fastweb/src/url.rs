@@ -0,0 +1,98 @@
+use crate::{EMPTY, PATH_SEPARATOR, QUERY_PARAM_KEY_VALUE_SEPARATOR, QUERY_PARAM_SEPARATOR};
+
+// Characters that never need escaping in a URL (RFC 3986 "unreserved").
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~')
+}
+
+/// Percent-encodes a value so it can be used as a single path segment.
+pub fn encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+
+    for byte in value.bytes() {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+
+    encoded
+}
+
+/// Decodes `%XX` escapes, leaving malformed escapes untouched. Returns `None`
+/// if the decoded bytes are not valid UTF-8.
+pub fn decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let escape = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+/// Decodes a component of a query string or `application/x-www-form-urlencoded`
+/// body, where `+` stands for a space.
+pub fn decode_component(value: &str) -> Option<String> {
+    decode(&value.replace('+', " "))
+}
+
+/// Parses `name=value` pairs separated by `&`, as found in query strings and
+/// form bodies. Repeated names are kept in order; a name without `=` has an
+/// empty value. Components that do not decode to UTF-8 are kept as sent.
+pub fn parse_pairs(input: &str) -> Vec<(String, String)> {
+    let decode =
+        |component: &str| decode_component(component).unwrap_or_else(|| component.to_string());
+
+    input
+        .split(QUERY_PARAM_SEPARATOR)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair
+                .split_once(QUERY_PARAM_KEY_VALUE_SEPARATOR)
+                .unwrap_or((pair, EMPTY));
+            (decode(name), decode(value))
+        })
+        .collect()
+}
+
+/// Normalizes a request path: repeated separators are collapsed, `.` and `..`
+/// segments are resolved (RFC 3986 section 5.2.4) without ever climbing above
+/// the root, and any trailing separator is dropped.
+pub fn normalize(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+
+    for segment in path.split(PATH_SEPARATOR) {
+        match segment.to_ascii_lowercase().as_str() {
+            "" | "." | "%2e" => continue,
+            ".." | ".%2e" | "%2e." | "%2e%2e" => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+
+    format!("{}{}", PATH_SEPARATOR, segments.join(PATH_SEPARATOR))
+}
+
+#[cfg(test)]
+#[path = "./url_test.rs"]
+mod test;
@@ -0,0 +1,69 @@
+#[cfg(test)]
+mod tests {
+    use super::super::{decode, decode_component, encode, normalize, parse_pairs};
+
+    #[test]
+    fn test_encode_unreserved() {
+        assert_eq!(encode("abc-XYZ_0.9~"), "abc-XYZ_0.9~");
+    }
+
+    #[test]
+    fn test_encode_reserved() {
+        assert_eq!(encode("a b/c?d"), "a%20b%2Fc%3Fd");
+    }
+
+    #[test]
+    fn test_encode_utf8() {
+        assert_eq!(encode("é"), "%C3%A9");
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("a%20b%2Fc").unwrap(), "a b/c");
+        assert_eq!(decode("%C3%A9").unwrap(), "é");
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        assert_eq!(decode("100%").unwrap(), "100%");
+        assert_eq!(decode("%zz").unwrap(), "%zz");
+        assert!(decode("%FF").is_none());
+    }
+
+    #[test]
+    fn test_normalize_separators() {
+        assert_eq!(normalize("/a//b/"), "/a/b");
+        assert_eq!(normalize("/"), "/");
+        assert_eq!(normalize(""), "/");
+    }
+
+    #[test]
+    fn test_normalize_dot_segments() {
+        assert_eq!(normalize("/a/./b"), "/a/b");
+        assert_eq!(normalize("/a/b/../c"), "/a/c");
+        assert_eq!(normalize("/a/%2E%2e/b"), "/b");
+    }
+
+    #[test]
+    fn test_normalize_above_root() {
+        assert_eq!(normalize("/../../etc/passwd"), "/etc/passwd");
+    }
+
+    #[test]
+    fn test_decode_component() {
+        assert_eq!(decode_component("a+b%2Bc").unwrap(), "a b+c");
+    }
+
+    #[test]
+    fn test_parse_pairs() {
+        let pairs = parse_pairs("q=rust+web&&x=a%3Db=c&flag&q=2");
+        let pairs: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        assert_eq!(
+            pairs,
+            vec![("q", "rust web"), ("x", "a=b=c"), ("flag", ""), ("q", "2")]
+        );
+    }
+}
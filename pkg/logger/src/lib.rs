@@ -1,4 +1,3 @@
-use chrono;
 use colored::{self, Colorize};
 use std::fmt;
 use std::sync::Once;
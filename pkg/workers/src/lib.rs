@@ -1,4 +1,6 @@
+use std::panic::{self, AssertUnwindSafe};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread;
 use std::sync::mpsc;
 use logger::{self, info, warn};
@@ -12,6 +14,7 @@ enum Message {
 pub struct ThreadPool {
     workers: Vec<Worker>,
     sender: mpsc::Sender<Message>,
+    pending: Arc<AtomicUsize>,
 }
 
 impl ThreadPool {
@@ -23,20 +26,27 @@ impl ThreadPool {
 
         let (sender, receiver) = mpsc::channel();
         let receiver = Arc::new(Mutex::new(receiver));
+        let pending = Arc::new(AtomicUsize::new(0));
 
         let mut workers = Vec::with_capacity(size);
 
         for id in 0..size {
-            workers.push(Worker::new(id, Arc::clone(&receiver)));
+            workers.push(Worker::new(id, Arc::clone(&receiver), Arc::clone(&pending)));
         }
 
-        ThreadPool { workers, sender }
+        ThreadPool { workers, sender, pending }
+    }
+
+    /// Number of jobs waiting for a free worker or running.
+    pub fn pending(&self) -> usize {
+        self.pending.load(Ordering::SeqCst)
     }
 
     pub fn execute<F>(&self, f: F)
     where
         F: FnOnce() + Send +'static,
     {
+        self.pending.fetch_add(1, Ordering::SeqCst);
         let job = Box::new(f);
         self.sender.send(Message::NewJob(job)).unwrap();
     }
@@ -65,6 +75,15 @@ impl Drop for ThreadPool {
     }
 }
 
+// Counts a job as done when dropped, even while unwinding from a panic.
+struct PendingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 struct Worker {
     id: usize,
     thread: Option<thread::JoinHandle<()>>
@@ -72,14 +91,24 @@ struct Worker {
 
 impl Worker {
     
-    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>) -> Worker {
+    fn new(id: usize, receiver: Arc<Mutex<mpsc::Receiver<Message>>>, pending: Arc<AtomicUsize>) -> Worker {
         let thread = thread::spawn(move || loop {
             let job = receiver.lock().unwrap().recv().unwrap();
 
             match job {
                 Message::NewJob(job) => {
+                    let _pending = PendingGuard(&pending);
                     logger::debug(format!("Worker {} executing job.", id).as_str());
-                    job();
+
+                    // A panicking job must not take the worker down with it
+                    if let Err(panic) = panic::catch_unwind(AssertUnwindSafe(job)) {
+                        let message = panic
+                            .downcast_ref::<&str>()
+                            .map(|message| message.to_string())
+                            .or_else(|| panic.downcast_ref::<String>().cloned())
+                            .unwrap_or_default();
+                        logger::error(format!("Worker {} job panicked: {}", id, message).as_str());
+                    }
                 }
                 Message::Terminate => {
                     logger::warn(format!("Worker {} received terminate signal.", id).as_str());
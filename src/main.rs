@@ -2,7 +2,6 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use badserde::json::Serde;
-use fastweb;
 use fastweb::handler;
 use fastweb::http::HttpStatus;
 use fastweb::request::Request;
@@ -15,10 +14,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     router.get(
         "/ping",
-        handler!(|r: Request| {
+        handler!(|_r: Request| {
             let content = String::from("pong");
             // println!("{:?}", r);
-            return fastweb::response::text(HttpStatus::StatusOK, content);
+            fastweb::response::text(HttpStatus::StatusOK, content)
         }),
     );
 
@@ -44,24 +43,28 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 content.insert(key.to_string(), value.to_string());
             }
 
-            return fastweb::response::json(HttpStatus::StatusOK, content);
+            fastweb::response::json(HttpStatus::StatusOK, content)
         }),
     );
 
     router.post(
         "/ping",
-        handler!(|r: Request| {
+        handler!(|_r: Request| {
             let content = String::from("pong");
-            return fastweb::response::text(HttpStatus::StatusOK, content);
+            fastweb::response::text(HttpStatus::StatusOK, content)
         }),
     );
 
+    router.static_files("/assets", "public");
+
     router
         .host(String::from("0.0.0.0"))
         .port(8080)
         .buffer_size(1024)
         .workers(5)
-        .serve()?;
+        .handle_signals(true)
+        .bind()?
+        .run();
 
     Ok(())
 }